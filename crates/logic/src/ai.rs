@@ -1,38 +1,75 @@
 use bevy::prelude::*;
-use map_bevy::Map;
+use map::{pathfinding, RoomId};
+use map_bevy::{Corridors, Fog};
 use rand::Rng;
 
-use crate::{in_game, movement::Unit};
+use crate::{
+    fog::AI_FACTION,
+    in_game,
+    movement::{self, Unit, PLAYER_SPEED},
+    pickups::Pickup,
+    Map,
+};
 
 pub struct AIPlugin;
 
 impl Plugin for AIPlugin {
     fn build(&self, app: &mut App) {
-        app.add_system(random_ai_move);
+        app.add_system(ai_move);
     }
 }
 
 #[derive(Component)]
 pub struct Ai;
 
-fn random_ai_move(
-    mut maps: Query<&mut Map>,
+/// Heads for the known pickup reached first, or wanders through known connections when none
+/// can be reached.
+///
+/// Waits for the fog of war to tell what the AI knows before moving at all.
+fn ai_move(
+    maps: Query<(&Map, Option<&Fog>, Option<&Corridors>)>,
+    pickups: Query<&Pickup>,
     mut random: ResMut<in_game::RandomDeterministic>,
     mut ais: Query<&mut Unit, With<Ai>>,
 ) {
+    let (map, fog, corridors) = match maps.get_single() {
+        Ok(map) => map,
+        Err(_) => return,
+    };
+    let knowledge = match fog.and_then(|f| f.0.knowledge(AI_FACTION)) {
+        Some(knowledge) => knowledge,
+        None => return,
+    };
     for mut u in ais.iter_mut() {
         if u.moving_to.is_some() {
-            return;
+            continue;
         }
-        if let Ok(map) = maps.get_single() {
-            let current_room = &map.0.rooms[&u.room_id];
-            if current_room.connections.is_empty() {
+        // Same costs as movement, only through the connections the AI discovered.
+        let plan = pickups
+            .iter()
+            .filter(|pickup| knowledge.is_discovered(pickup.room_id))
+            .filter_map(|pickup| {
+                pathfinding::fastest_path_along(
+                    &map.0,
+                    u.room_id,
+                    pickup.room_id,
+                    PLAYER_SPEED,
+                    |from, to| knowledge.is_edge_discovered(from, to),
+                    |from, to| movement::walk_length(&map.0, corridors, from, to),
+                )
+            })
+            .filter_map(|(path, _)| Some((u.estimated_arrival(&map.0, corridors, &path)?, path)))
+            .min_by(|(a, _), (b, _)| a.total_cmp(b));
+        if let Some((_, path)) = plan {
+            if path.len() > 1 {
+                u.moving_to = Some(path[1]);
                 continue;
             }
-            u.moving_to = Some(
-                current_room.connections
-                    [random.random.gen_range((0..current_room.connections.len()))],
-            );
         }
+        let connections: Vec<RoomId> = knowledge.known_connections(u.room_id).collect();
+        if connections.is_empty() {
+            continue;
+        }
+        u.moving_to = Some(connections[random.random.gen_range(0..connections.len())]);
     }
 }
@@ -0,0 +1,40 @@
+use bevy::prelude::*;
+use map::fog::Faction;
+use map_bevy::{Fog, FogViewer};
+
+use crate::{ai::Ai, movement::Unit, spawn_elements::Player, Map};
+
+pub const PLAYER_FACTION: Faction = Faction(0);
+pub const AI_FACTION: Faction = Faction(1);
+/// Connections units see through.
+pub const VISION_RANGE: usize = 1;
+
+pub struct FogPlugin;
+
+impl Plugin for FogPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(FogViewer {
+            faction: Some(PLAYER_FACTION),
+        });
+        app.add_system(update_fog);
+    }
+}
+
+/// Units see from their room, and from the one they are moving to.
+fn observed_rooms<'a>(units: impl Iterator<Item = &'a Unit>) -> Vec<map::RoomId> {
+    units
+        .flat_map(|u| std::iter::once(u.room_id).chain(u.moving_to))
+        .collect()
+}
+
+fn update_fog(
+    mut maps: Query<(&Map, &mut Fog)>,
+    players: Query<&Unit, With<Player>>,
+    ais: Query<&Unit, With<Ai>>,
+) {
+    for (map, mut fog) in maps.iter_mut() {
+        fog.0
+            .update(&map.0, PLAYER_FACTION, observed_rooms(players.iter()));
+        fog.0.update(&map.0, AI_FACTION, observed_rooms(ais.iter()));
+    }
+}
@@ -1,4 +1,5 @@
 mod ai;
+mod fog;
 mod map_builder;
 mod movement;
 mod pickups;
@@ -12,17 +13,22 @@ use bevy::{
 };
 use camera_pan::CameraPanPlugin;
 use end_game::check_no_pickups;
+use fog::FogPlugin;
 use input::InputCamera;
-use map_bevy::{DisplayMap, Map, MapPlugin};
-use map_builder::MapBuilder;
+use map_bevy::{DisplayMap, MapCorePlugin, MapRenderPlugin};
+use map_builder::{MapBuilder, RoomKind};
 use movement::MovementPlugin;
 use pickups::unit_pickup_on_move_finished;
 use rand::{Rng, SeedableRng};
 use rand_chacha::ChaCha20Rng;
 use selection::SelectionPlugin;
+use shapes::ShapesPlugin;
 use spawn_elements::spawn_elements;
 use wasm_bindgen::prelude::*;
 
+/// Maps of the game.
+pub(crate) type Map = map_bevy::Map<RoomKind>;
+
 #[wasm_bindgen]
 pub fn run() {
     App::new().add_plugin(LogicPlugin).run();
@@ -42,15 +48,19 @@ pub enum GameState {
 
 impl Plugin for LogicPlugin {
     fn build(&self, app: &mut App) {
+        app.add_plugins(DefaultPlugins);
         #[cfg(debug_assertions)]
         app.add_plugin(LogDiagnosticsPlugin::default())
             .add_plugin(FrameTimeDiagnosticsPlugin::default());
 
-        app.add_plugin(MapPlugin);
+        app.add_plugin(MapCorePlugin::<RoomKind>::default());
+        app.add_plugin(MapRenderPlugin::new(map_builder::room_style));
+        app.add_plugin(ShapesPlugin);
         app.add_plugin(CameraPanPlugin);
         app.add_plugin(SelectionPlugin);
         app.add_plugin(MovementPlugin);
         app.add_plugin(AIPlugin);
+        app.add_plugin(FogPlugin);
         app.add_state(GameState::LoadingBasic);
 
         app.insert_resource(in_game::RandomDeterministic::default());
@@ -76,11 +86,12 @@ pub mod in_game {
     use super::movement::EventPlayersSpawn;
     use super::movement::Unit;
     use super::spawn_elements::Player;
+    use crate::map_builder::RoomKind;
+    use crate::Map;
     use bevy::prelude::*;
     use camera_pan::CameraPan;
     use input::InputCamera;
-    use map_bevy::Map;
-    use map_bevy::RoomEntity;
+    use map_bevy::{Corridors, RoomEntity};
     use rand::{thread_rng, Rng, SeedableRng};
     use rand_chacha::ChaCha20Rng;
     use selection::Selectable;
@@ -167,7 +178,11 @@ pub mod in_game {
                 let from_room = id.room_id;
                 for (mut map, mut builder) in maps.iter_mut() {
                     for _ in 0..2 {
-                        if map.0.add(from_room, 1, &mut random.random, 15).is_ok() {
+                        if map
+                            .0
+                            .add(from_room, RoomKind::Plain, &mut random.random, 15)
+                            .is_ok()
+                        {
                             return;
                         }
                     }
@@ -179,7 +194,7 @@ pub mod in_game {
     pub(crate) fn move_to_selected_rooms(
         mut commands: Commands,
         q_selected_rooms: Query<(Entity, &RoomEntity, &Selectable), With<RoomEntity>>,
-        mut maps: Query<&mut Map>,
+        maps: Query<(&Map, Option<&Corridors>)>,
         mut players: Query<&mut Unit, With<Player>>,
     ) {
         for (e, id, s) in q_selected_rooms.iter() {
@@ -187,13 +202,15 @@ pub mod in_game {
                 let mut player = players.single_mut();
                 if let Some(moving_to) = player.moving_to {
                     if id.room_id == player.room_id {
-                        player.moving_to = Some(id.room_id);
+                        if let Ok((map, corridors)) = maps.get_single() {
+                            player.turn_back(&map.0, corridors);
+                        }
                     }
                     break;
                 } else if id.room_id == player.room_id {
                     break;
                 }
-                if let Ok(map) = maps.get_single() {
+                if let Ok((map, _)) = maps.get_single() {
                     let current_room = &map.0.rooms[&player.room_id];
                     if !current_room.connections.contains(&id.room_id) {
                         break;
@@ -207,9 +224,8 @@ pub mod in_game {
 
 mod end_game {
     use bevy::prelude::*;
-    use map_bevy::Map;
 
-    use crate::{movement::UnitFinishedMove, pickups::Pickup, GameState};
+    use crate::{movement::UnitFinishedMove, pickups::Pickup, GameState, Map};
 
     pub fn check_no_pickups(
         mut commands: Commands,
@@ -1,24 +1,40 @@
-use std::{collections::HashMap, time::Duration};
+use std::time::Duration;
 
 use bevy::{ecs::component::TableStorage, prelude::*};
-use map::{Room, RoomId};
-use map_bevy::{DisplayMap, Map, RoomEntity};
-use rand::Rng;
+use map::{fog::FogOfWar, generator::Generator, RoomId};
+use map_bevy::{Corridors, DisplayConnections, DisplayMap, Fog, RoomEntity, RoomShape, RoomStyle};
 use selection::Selectable;
 
 use crate::{
+    fog::VISION_RANGE,
     in_game::{self, RandomDeterministic},
     movement::EventPlayersSpawn,
-    GameState,
+    GameState, Map,
 };
 
-#[derive(Default)]
-struct RoomClutter {
-    pub nb_gen_tries: u8,
+/// Data of the rooms of the game maps.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RoomKind {
+    /// The first room generated.
+    Start,
+    Plain,
 }
+
+pub(crate) fn room_style(map: &map::Map<RoomKind>, id: RoomId) -> RoomStyle {
+    match map.rooms[&id].data {
+        RoomKind::Start => RoomStyle {
+            shape: RoomShape::Hexagon,
+            size: 18.0,
+            color: Color::ORANGE,
+            ..Default::default()
+        },
+        RoomKind::Plain => RoomStyle::default(),
+    }
+}
+
 #[derive(Default, Component)]
 pub struct MapBuilder {
-    clutters: HashMap<RoomId, RoomClutter>,
+    generator: Generator,
 }
 
 pub fn setup(app: &mut App) {
@@ -41,6 +57,9 @@ fn setup_map(
     commands
         .spawn()
         .insert(DisplayMap::default())
+        .insert(DisplayConnections::default())
+        .insert(Corridors::default())
+        .insert(Fog(FogOfWar::new(VISION_RANGE)))
         .insert(MapBuilder::default())
         .insert(map);
     game_state.set(dbg!(GameState::LoadingMapRooms));
@@ -58,7 +77,7 @@ fn create_level(
     }
     for (mut map, mut builder) in maps.iter_mut() {
         dbg!("create level");
-        for _ in 0..25 {
+        for _ in 0..builder.generator.config.rooms {
             create_room(map.as_mut(), builder.as_mut(), &mut random);
         }
     }
@@ -92,62 +111,12 @@ fn create_room(
     mut builder: &mut MapBuilder,
     random: &mut ResMut<RandomDeterministic>,
 ) {
-    for _ in 0..5 {
-        let mut filtered_rooms: Vec<(&RoomId, &Room<i32>)> = map
-            .0
-            .iter()
-            .filter(|r| match builder.clutters.get(r.0) {
-                Some(clutter) => clutter.nb_gen_tries <= 1,
-                None => true,
-            })
-            .collect();
-        if filtered_rooms.is_empty() {
-            builder.clutters.clear();
-            filtered_rooms = map.0.iter().collect();
-        }
-        if filtered_rooms.is_empty() {
-            map.0.create_raw(
-                0,
-                (
-                    random.random.gen_range(-1f32..=1f32) * 30f32,
-                    random.random.gen_range(-1f32..=1f32) * 30f32,
-                ),
-                vec![],
-            );
-            break;
-        }
-        let random_index = random.random.gen_range(0..filtered_rooms.len());
-
-        let (from_room, _) = filtered_rooms[random_index];
-        let from_room = *from_room;
-        match map.0.add(from_room, 1, &mut random.random, 10) {
-            Ok(room_id) => {
-                let pos_new: Vec2 = map.0.rooms[&room_id].position.into();
-                let mut to_connect = vec![];
-                for other_room in map.0.iter() {
-                    if *other_room.0 == room_id {
-                        continue;
-                    }
-                    let pos_other: Vec2 = map.0.rooms[other_room.0].position.into();
-                    if pos_new.distance(pos_other) < 50f32 {
-                        to_connect.push((*other_room.0, room_id));
-                    }
-                }
-                for (id_1, id_2) in to_connect {
-                    map.0.connect(id_1, id_2);
-                    map.0.connect(id_2, id_1);
-                }
-                break;
-            }
-            Err(_) => {
-                builder
-                    .clutters
-                    .entry(from_room)
-                    .or_insert_with(RoomClutter::default)
-                    .nb_gen_tries += 1;
-            }
-        }
-    }
+    let data = if map.0.is_empty() {
+        RoomKind::Start
+    } else {
+        RoomKind::Plain
+    };
+    builder.generator.grow(&mut map.0, data, &mut random.random);
 }
 
 fn make_rooms_selectable(mut commands: Commands, q_new_rooms: Query<Entity, Added<RoomEntity>>) {
@@ -1,13 +1,13 @@
 use bevy::{
     ecs::component::TableStorage, math::Vec3Swizzles, prelude::*, sprite::MaterialMesh2dBundle,
 };
-use map::RoomId;
-use map_bevy::{Map, RoomEntity};
+use map::{cost, RoomId};
+use map_bevy::{Corridors, RoomEntity};
 use shapes::ShapeMeshes;
 
-use crate::in_game::RandomDeterministic;
+use crate::{in_game::RandomDeterministic, Map};
 
-const PLAYER_SPEED: f32 = 60f32;
+pub const PLAYER_SPEED: f32 = 60f32;
 
 pub struct MovementPlugin;
 
@@ -25,36 +25,137 @@ pub struct UnitFinishedMove {
     pub arrived_at: RoomId,
 }
 
-#[derive(Component)]
+#[derive(Component, Default)]
 pub struct Unit {
     pub room_id: RoomId,
     pub moving_to: Option<RoomId>,
+    /// Seconds spent on the current move, opening the door first then walking.
+    pub progress: f32,
+}
+
+/// Length walked from `from` to `to`: along the corridor once routed, straight before.
+pub fn walk_length<T>(
+    map: &map::Map<T>,
+    corridors: Option<&Corridors>,
+    from: RoomId,
+    to: RoomId,
+) -> f32 {
+    corridors
+        .and_then(|corridors| corridors.length(from, to))
+        .or_else(|| cost::edge_length(map, from, to))
+        .unwrap_or(0f32)
+}
+
+/// Seconds for a unit to go from `from` to `to`, the cost AI planning uses too.
+pub fn travel_time<T>(
+    map: &map::Map<T>,
+    corridors: Option<&Corridors>,
+    from: RoomId,
+    to: RoomId,
+) -> f32 {
+    let length = walk_length(map, corridors, from, to);
+    cost::travel_time_over(map, from, to, length, PLAYER_SPEED)
+}
+
+impl Unit {
+    pub fn new(room_id: RoomId) -> Self {
+        Self {
+            room_id,
+            ..Default::default()
+        }
+    }
+
+    /// Seconds left before reaching `moving_to`, 0 when not moving.
+    pub fn remaining_time<T>(&self, map: &map::Map<T>, corridors: Option<&Corridors>) -> f32 {
+        match self.moving_to {
+            Some(moving_to) => {
+                let total = travel_time(map, corridors, self.room_id, moving_to);
+                (total - self.progress).max(0f32)
+            }
+            None => 0f32,
+        }
+    }
+
+    /// Seconds before reaching the end of `path`, starting from where the unit is heading.
+    ///
+    /// Uses the same costs as [`map::pathfinding::fastest_path_along`] with [`walk_length`].
+    pub fn estimated_arrival<T>(
+        &self,
+        map: &map::Map<T>,
+        corridors: Option<&Corridors>,
+        path: &[RoomId],
+    ) -> Option<f32> {
+        let start = self.moving_to.unwrap_or(self.room_id);
+        let path = match path.iter().position(|room| *room == start) {
+            Some(index) => &path[index..],
+            None => return None,
+        };
+        let steps: f32 = path
+            .windows(2)
+            .map(|step| travel_time(map, corridors, step[0], step[1]))
+            .sum();
+        Some(self.remaining_time(map, corridors) + steps)
+    }
+
+    /// Share of the corridor already walked, from 0 to 1.
+    fn walked<T>(
+        &self,
+        map: &map::Map<T>,
+        corridors: Option<&Corridors>,
+        moving_to: RoomId,
+    ) -> f32 {
+        let door_time = map.edge_data(self.room_id, moving_to).door_time;
+        let total = travel_time(map, corridors, self.room_id, moving_to);
+        if total - door_time <= 0f32 {
+            return 1f32;
+        }
+        ((self.progress - door_time) / (total - door_time)).clamp(0f32, 1f32)
+    }
+
+    /// Heads back to the room the unit comes from, from where it stands in the corridor.
+    pub fn turn_back<T>(&mut self, map: &map::Map<T>, corridors: Option<&Corridors>) {
+        let moving_to = match self.moving_to {
+            Some(moving_to) => moving_to,
+            None => return,
+        };
+        let walked = self.walked(map, corridors, moving_to);
+        let door_time = map.edge_data(moving_to, self.room_id).door_time;
+        let total = travel_time(map, corridors, moving_to, self.room_id);
+        // The door was already opened on the way in.
+        self.progress = door_time + (1f32 - walked) * (total - door_time);
+        self.moving_to = Some(self.room_id);
+        self.room_id = moving_to;
+    }
 }
 
 fn update_units_position(
     time: Res<Time>,
-    mut maps: Query<&Map>,
+    maps: Query<(&Map, Option<&Corridors>)>,
     mut units: Query<(Entity, &mut Transform, &mut Unit)>,
     mut event_unit_finished_move: EventWriter<UnitFinishedMove>,
 ) {
-    let mut map = maps.get_single_mut();
-    if map.is_err() {
-        return;
-    }
-    let mut map = map.unwrap();
+    let (map, corridors) = match maps.get_single() {
+        Ok(map) => map,
+        Err(_) => return,
+    };
     for (e, mut t, mut u) in units.iter_mut() {
         if let Some(moving_to) = u.moving_to {
-            let target = &map.0.rooms[&moving_to];
-            let target: Vec2 = target.position.into();
-            let to_target: Vec2 = target - t.translation.xy();
-            let actual_move = to_target
-                .clamp_length_max(PLAYER_SPEED * time.delta_seconds())
-                .extend(0f32);
-            t.translation += actual_move;
+            let (from, target) = match (map.0.rooms.get(&u.room_id), map.0.rooms.get(&moving_to)) {
+                (Some(from), Some(target)) => (from.position, target.position),
+                _ => continue,
+            };
+            u.progress += time.delta_seconds();
+            let walked = u.walked(&map.0, corridors, moving_to);
+            let position = corridors
+                .and_then(|corridors| corridors.point_at(u.room_id, moving_to, walked))
+                .map(Vec2::from)
+                .unwrap_or_else(|| Vec2::from(from).lerp(target.into(), walked));
+            t.translation = position.extend(t.translation.z);
 
-            if t.translation.xy() == target {
+            if u.remaining_time(&map.0, corridors) <= 0f32 {
                 u.room_id = moving_to;
                 u.moving_to = None;
+                u.progress = 0f32;
                 event_unit_finished_move.send(UnitFinishedMove {
                     entity: e,
                     arrived_at: u.room_id,
@@ -1,10 +1,9 @@
 use bevy::prelude::*;
 use map::RoomId;
-use map_bevy::Map;
 
 use crate::{
     movement::{Unit, UnitFinishedMove},
-    GameState,
+    GameState, Map,
 };
 
 #[derive(Component)]
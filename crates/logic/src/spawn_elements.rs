@@ -1,11 +1,14 @@
 use bevy::{ecs::component::TableStorage, prelude::*, sprite::MaterialMesh2dBundle};
-use map::RoomId;
-use map_bevy::{Map, RoomEntity};
+use map::{
+    placement::{self, Constraint, PlacementRequest},
+    RoomId,
+};
+use map_bevy::RoomEntity;
 use shapes::ShapeMeshes;
 
 use crate::ai::Ai;
-use crate::GameState;
 use crate::{in_game::RandomDeterministic, movement::Unit, pickups::Pickup};
+use crate::{GameState, Map};
 
 use crate::movement::EventPlayersSpawn;
 
@@ -50,6 +53,35 @@ fn create_point_bundle(shapes: &Res<ShapeMeshes>, pos: (f32, f32)) -> UnitGraphi
     UnitGraphics { mesh_bundle: mesh }
 }
 
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+enum SpawnRole {
+    Player,
+    Ai,
+    Pickup,
+}
+
+fn spawn_request() -> PlacementRequest<SpawnRole> {
+    PlacementRequest::new(
+        vec![
+            (SpawnRole::Player, 1),
+            (SpawnRole::Ai, 2),
+            (SpawnRole::Pickup, 4),
+        ],
+        vec![
+            Constraint::MinHops {
+                role: SpawnRole::Pickup,
+                from: SpawnRole::Player,
+                hops: 3,
+            },
+            Constraint::MinHops {
+                role: SpawnRole::Ai,
+                from: SpawnRole::Player,
+                hops: 3,
+            },
+        ],
+    )
+}
+
 struct SpawnDef {
     players: Vec<RoomId>,
     points: Vec<RoomId>,
@@ -87,20 +119,24 @@ pub fn spawn_elements(
     if map.0.is_empty() {
         return;
     }
+    let placement = match placement::place(&map.0, &spawn_request(), &mut random.random) {
+        Ok(placement) => placement,
+        Err(error) => {
+            warn!("Could not place units, re-rolling the level: {}", error);
+            game_state.set(GameState::NotPlaying);
+            return;
+        }
+    };
     game_state.set(GameState::Playing);
     dbg!("spawning");
     let spawn_def = SpawnDef {
-        players: vec![
-            *map.0.iter().next().unwrap().0,
-            *map.0.iter().nth(2).unwrap().0,
-            *map.0.iter().last().unwrap().0,
-        ],
-        points: vec![
-            *map.0.iter().nth(3).unwrap().0,
-            *map.0.iter().nth(4).unwrap().0,
-            *map.0.iter().nth(5).unwrap().0,
-            *map.0.iter().nth(6).unwrap().0,
-        ],
+        players: placement
+            .rooms_of(&SpawnRole::Player)
+            .iter()
+            .chain(placement.rooms_of(&SpawnRole::Ai))
+            .copied()
+            .collect(),
+        points: placement.rooms_of(&SpawnRole::Pickup).to_vec(),
     };
 
     spawn_def.players.iter().enumerate().for_each(|(i, r)| {
@@ -142,11 +178,8 @@ fn spawn_unit(
     if let Some(room) = rooms.iter().find(|(e, r)| r.room_id == room_id) {
         let mut u = commands.spawn();
 
-        u.insert(Unit {
-            room_id,
-            moving_to: None,
-        })
-        .insert_bundle(graphics.mesh_bundle);
+        u.insert(Unit::new(room_id))
+            .insert_bundle(graphics.mesh_bundle);
         if is_player {
             u.insert(Player);
         } else {
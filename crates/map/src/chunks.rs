@@ -0,0 +1,252 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+use rand::SeedableRng;
+use rand_chacha::ChaCha20Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    generator::{Generator, GeneratorConfig},
+    Map, RoomId, ROOM_SPACING,
+};
+
+#[derive(
+    PartialOrd, Ord, PartialEq, Eq, Hash, Default, Clone, Copy, Debug, Serialize, Deserialize,
+)]
+pub struct ChunkCoord {
+    pub x: i32,
+    pub y: i32,
+}
+
+impl ChunkCoord {
+    pub fn new(x: i32, y: i32) -> Self {
+        Self { x, y }
+    }
+
+    pub fn containing(position: (f32, f32), chunk_size: f32) -> Self {
+        Self {
+            x: (position.0 / chunk_size).floor() as i32,
+            y: (position.1 / chunk_size).floor() as i32,
+        }
+    }
+
+    pub fn center(&self, chunk_size: f32) -> (f32, f32) {
+        (
+            (self.x as f32 + 0.5f32) * chunk_size,
+            (self.y as f32 + 0.5f32) * chunk_size,
+        )
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChunkConfig {
+    pub world_seed: u64,
+    pub chunk_size: f32,
+    /// Chunks loaded around the center chunk, in chunks: 1 loads a 3x3 square.
+    pub load_radius: i32,
+    /// Facing rooms of neighbouring chunks closer than this are connected.
+    pub stitch_distance: f32,
+    pub generator: GeneratorConfig,
+}
+
+impl Default for ChunkConfig {
+    fn default() -> Self {
+        Self {
+            world_seed: 0,
+            chunk_size: 300f32,
+            load_radius: 1,
+            stitch_distance: 120f32,
+            generator: GeneratorConfig {
+                rooms: 40,
+                spawn_extent: 0f32,
+                ..Default::default()
+            },
+        }
+    }
+}
+
+/// Chunks loaded and unloaded by [`ChunkedWorld::update`].
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct ChunkChanges {
+    pub loaded: Vec<ChunkCoord>,
+    pub unloaded: Vec<ChunkCoord>,
+}
+
+/// Endless map, generated chunk by chunk around a moving center.
+///
+/// Every chunk is generated from its own seed and stitched to each neighbour on its own, so the
+/// world doesn't depend on the order chunks are loaded in. Room ids are never reused: a room
+/// stays valid until its chunk is unloaded, and chunks holding a pinned room are kept loaded.
+#[derive(Debug, Default)]
+pub struct ChunkedWorld {
+    pub config: ChunkConfig,
+    loaded: BTreeMap<ChunkCoord, Vec<RoomId>>,
+    room_chunks: HashMap<RoomId, ChunkCoord>,
+}
+
+impl ChunkedWorld {
+    pub fn new(config: ChunkConfig) -> Self {
+        Self {
+            config,
+            loaded: BTreeMap::new(),
+            room_chunks: HashMap::new(),
+        }
+    }
+
+    pub fn chunk_of(&self, room: RoomId) -> Option<ChunkCoord> {
+        self.room_chunks.get(&room).copied()
+    }
+
+    pub fn is_loaded(&self, chunk: ChunkCoord) -> bool {
+        self.loaded.contains_key(&chunk)
+    }
+
+    pub fn loaded_chunks(&self) -> impl Iterator<Item = &ChunkCoord> {
+        self.loaded.keys()
+    }
+
+    pub fn rooms_in(&self, chunk: ChunkCoord) -> &[RoomId] {
+        self.loaded
+            .get(&chunk)
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+    }
+
+    /// Seed of a chunk, derived from the world seed.
+    pub fn chunk_seed(&self, chunk: ChunkCoord) -> u64 {
+        let mut seed = self.config.world_seed;
+        for value in [chunk.x as u32 as u64, chunk.y as u32 as u64] {
+            seed = splitmix64(seed ^ splitmix64(value));
+        }
+        seed
+    }
+
+    /// Loads chunks around `center` and unloads the others, except those holding `pinned` rooms.
+    pub fn update<T: Clone>(
+        &mut self,
+        map: &mut Map<T>,
+        center: (f32, f32),
+        pinned: &[RoomId],
+        data: T,
+    ) -> ChunkChanges {
+        let center = ChunkCoord::containing(center, self.config.chunk_size);
+        let radius = self.config.load_radius;
+        let wanted: BTreeSet<ChunkCoord> = (-radius..=radius)
+            .flat_map(|dx| (-radius..=radius).map(move |dy| (dx, dy)))
+            .map(|(dx, dy)| ChunkCoord::new(center.x + dx, center.y + dy))
+            .collect();
+        let kept: BTreeSet<ChunkCoord> = pinned
+            .iter()
+            .filter_map(|room| self.chunk_of(*room))
+            .collect();
+
+        let mut changes = ChunkChanges::default();
+        let to_unload: Vec<ChunkCoord> = self
+            .loaded
+            .keys()
+            .filter(|chunk| !wanted.contains(chunk) && !kept.contains(chunk))
+            .copied()
+            .collect();
+        for chunk in to_unload {
+            self.unload_chunk(map, chunk);
+            changes.unloaded.push(chunk);
+        }
+        for chunk in wanted {
+            if self.load_chunk(map, chunk, data.clone()) {
+                changes.loaded.push(chunk);
+            }
+        }
+        changes
+    }
+
+    /// Generates a chunk and stitches it to its loaded neighbours, returns `false` if it was
+    /// already loaded.
+    pub fn load_chunk<T: Clone>(&mut self, map: &mut Map<T>, chunk: ChunkCoord, data: T) -> bool {
+        if self.loaded.contains_key(&chunk) {
+            return false;
+        }
+        let content = self.generate_chunk(chunk, data);
+        let mapping = map.merge(content, chunk.center(self.config.chunk_size), None);
+        let mut rooms: Vec<RoomId> = mapping.into_values().collect();
+        rooms.sort();
+        for room in rooms.iter() {
+            self.room_chunks.insert(*room, chunk);
+        }
+        self.loaded.insert(chunk, rooms);
+        self.stitch(map, chunk);
+        true
+    }
+
+    /// Connects the facing rooms of a chunk and of each of its loaded neighbours.
+    ///
+    /// Only the rooms of both chunks are considered, lowest chunk first, so the connections
+    /// don't depend on what else is loaded.
+    fn stitch<T>(&self, map: &mut Map<T>, chunk: ChunkCoord) {
+        let neighbours: BTreeSet<ChunkCoord> = (-1..=1)
+            .flat_map(|dx| (-1..=1).map(move |dy| ChunkCoord::new(chunk.x + dx, chunk.y + dy)))
+            .filter(|neighbour| *neighbour != chunk && self.is_loaded(*neighbour))
+            .collect();
+        for neighbour in neighbours {
+            let (low, high) = (chunk.min(neighbour), chunk.max(neighbour));
+            let pairs = map.facing_rooms(
+                self.rooms_in(low),
+                self.rooms_in(high),
+                self.config.stitch_distance,
+            );
+            for (a, b) in pairs {
+                let _ = map.connect(a, b);
+                let _ = map.connect(b, a);
+            }
+        }
+    }
+
+    /// Removes the rooms of a chunk, returns `false` if it wasn't loaded.
+    pub fn unload_chunk<T>(&mut self, map: &mut Map<T>, chunk: ChunkCoord) -> bool {
+        let rooms = match self.loaded.remove(&chunk) {
+            Some(rooms) => rooms,
+            None => return false,
+        };
+        for room in rooms {
+            self.room_chunks.remove(&room);
+            map.remove(room);
+        }
+        true
+    }
+
+    /// Chunk content centered on `(0, 0)`, only depending on the chunk seed.
+    fn generate_chunk<T: Clone>(&self, chunk: ChunkCoord, data: T) -> Map<T> {
+        let mut random = ChaCha20Rng::seed_from_u64(self.chunk_seed(chunk));
+        let mut content = Map::default();
+        Generator::new(self.config.generator.clone()).generate(&mut content, data, &mut random);
+
+        // Rooms stay half a spacing away from the borders, so rooms of neighbouring chunks are
+        // never too close, whatever order chunks are loaded in.
+        let half = self.config.chunk_size / 2f32 - ROOM_SPACING / 2f32;
+        let inside: Vec<RoomId> = content
+            .sorted_ids()
+            .into_iter()
+            .filter(|id| {
+                let (x, y) = content.rooms[id].position;
+                x.abs() <= half && y.abs() <= half
+            })
+            .collect();
+        let clipped = content.subgraph(&inside);
+        // Clipping may cut the chunk in pieces, only the one holding the first room is kept.
+        let reachable: Vec<RoomId> = match inside.first() {
+            Some(first) => {
+                let mut reachable: Vec<RoomId> =
+                    clipped.distances_from(*first).into_keys().collect();
+                reachable.sort();
+                reachable
+            }
+            None => vec![],
+        };
+        clipped.subgraph(&reachable)
+    }
+}
+
+fn splitmix64(mut value: u64) -> u64 {
+    value = value.wrapping_add(0x9E37_79B9_7F4A_7C15);
+    value = (value ^ (value >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    value = (value ^ (value >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    value ^ (value >> 31)
+}
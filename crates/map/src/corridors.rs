@@ -0,0 +1,208 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{Map, RoomId};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CorridorConfig {
+    /// Rooms are avoided as circles of this radius.
+    pub room_radius: f32,
+    /// Space kept between corridors and the rooms they go around.
+    pub clearance: f32,
+    /// Detours added at most per corridor, a corridor still blocked after that goes through.
+    pub max_detours: usize,
+    /// Points inserted between two corridor points to round it as a Catmull-Rom spline, 0 keeps
+    /// the polyline. Parts of the spline cutting into a room are kept straight.
+    pub smoothing: usize,
+}
+
+impl Default for CorridorConfig {
+    fn default() -> Self {
+        Self {
+            room_radius: 15f32,
+            clearance: 5f32,
+            max_detours: 8,
+            smoothing: 0,
+        }
+    }
+}
+
+/// Path of a connection, from the center of a room to the center of another.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Corridor {
+    pub points: Vec<(f32, f32)>,
+}
+
+impl Corridor {
+    pub fn length(&self) -> f32 {
+        self.points
+            .windows(2)
+            .map(|segment| distance(segment[0], segment[1]))
+            .sum()
+    }
+
+    /// Point at `t` of the corridor length, from 0 at its start to 1 at its end.
+    pub fn point_at(&self, t: f32) -> (f32, f32) {
+        let mut remaining = t.clamp(0f32, 1f32) * self.length();
+        for segment in self.points.windows(2) {
+            let length = distance(segment[0], segment[1]);
+            if remaining <= length && length > 0f32 {
+                return lerp(segment[0], segment[1], remaining / length);
+            }
+            remaining -= length;
+        }
+        self.points.last().copied().unwrap_or_default()
+    }
+
+    pub fn reversed(&self) -> Corridor {
+        Corridor {
+            points: self.points.iter().rev().copied().collect(),
+        }
+    }
+}
+
+/// Corridor from `from` to `to`, going around the other rooms.
+pub fn route<T>(
+    map: &Map<T>,
+    from: RoomId,
+    to: RoomId,
+    config: &CorridorConfig,
+) -> Option<Corridor> {
+    let (start, end) = (map.rooms.get(&from)?.position, map.rooms.get(&to)?.position);
+    let mut obstacles: Vec<(RoomId, (f32, f32))> = map
+        .rooms
+        .iter()
+        .filter(|(id, _)| **id != from && **id != to)
+        .map(|(id, room)| (*id, room.position))
+        .collect();
+    obstacles.sort_by_key(|(id, _)| *id);
+    let obstacles: Vec<(f32, f32)> = obstacles.into_iter().map(|(_, p)| p).collect();
+
+    let mut points = vec![start];
+    let mut detours = config.max_detours;
+    let radius = config.room_radius + config.clearance;
+    route_segment(start, end, &obstacles, radius, &mut detours, &mut points);
+    Some(Corridor {
+        points: smooth(&points, config.smoothing, &obstacles, radius),
+    })
+}
+
+/// One corridor per undirected edge, keyed and oriented from the lowest id to the highest.
+pub fn route_all<T>(map: &Map<T>, config: &CorridorConfig) -> BTreeMap<(RoomId, RoomId), Corridor> {
+    map.edges()
+        .into_iter()
+        .filter_map(|(a, b)| route(map, a, b, config).map(|corridor| ((a, b), corridor)))
+        .collect()
+}
+
+/// Pushes the points after `a` up to `b`, splitting the segment around the first room it
+/// crosses.
+fn route_segment(
+    a: (f32, f32),
+    b: (f32, f32),
+    obstacles: &[(f32, f32)],
+    radius: f32,
+    detours: &mut usize,
+    points: &mut Vec<(f32, f32)>,
+) {
+    let blocking = obstacles
+        .iter()
+        .filter_map(|obstacle| {
+            let t = projection(a, b, *obstacle);
+            let closest = lerp(a, b, t);
+            (t > 0f32 && t < 1f32 && distance(closest, *obstacle) < radius)
+                .then_some((t, closest, *obstacle))
+        })
+        .min_by(|(t1, ..), (t2, ..)| t1.total_cmp(t2));
+    let (closest, obstacle) = match blocking {
+        Some((_, closest, obstacle)) if *detours > 0 => (closest, obstacle),
+        _ => {
+            points.push(b);
+            return;
+        }
+    };
+    *detours -= 1;
+
+    let (mut dx, mut dy) = (closest.0 - obstacle.0, closest.1 - obstacle.1);
+    let mut length = (dx * dx + dy * dy).sqrt();
+    if length < f32::EPSILON {
+        // Going right through the center, turn left.
+        (dx, dy) = (a.1 - b.1, b.0 - a.0);
+        length = (dx * dx + dy * dy).sqrt();
+    }
+    // A little further, so both new segments clear the room after float rounding.
+    let away = radius * 1.05f32 / length;
+    let waypoint = (obstacle.0 + dx * away, obstacle.1 + dy * away);
+    route_segment(a, waypoint, obstacles, radius, detours, points);
+    route_segment(waypoint, b, obstacles, radius, detours, points);
+}
+
+/// Catmull-Rom spline through `points`, with `subdivisions` points inserted in each segment.
+///
+/// Segments whose curve comes closer than `radius` to an obstacle are left straight.
+fn smooth(
+    points: &[(f32, f32)],
+    subdivisions: usize,
+    obstacles: &[(f32, f32)],
+    radius: f32,
+) -> Vec<(f32, f32)> {
+    if subdivisions == 0 || points.len() < 3 {
+        return points.to_vec();
+    }
+    let at = |i: isize| points[i.clamp(0, points.len() as isize - 1) as usize];
+    let mut smoothed = vec![];
+    for i in 0..points.len() as isize - 1 {
+        let (p0, p1, p2, p3) = (at(i - 1), at(i), at(i + 1), at(i + 2));
+        let mut curve = vec![p1];
+        for step in 1..=subdivisions {
+            let t = step as f32 / (subdivisions + 1) as f32;
+            let (t2, t3) = (t * t, t * t * t);
+            let axis = |p0: f32, p1: f32, p2: f32, p3: f32| {
+                0.5f32
+                    * (2f32 * p1
+                        + (p2 - p0) * t
+                        + (2f32 * p0 - 5f32 * p1 + 4f32 * p2 - p3) * t2
+                        + (3f32 * p1 - p0 - 3f32 * p2 + p3) * t3)
+            };
+            curve.push((axis(p0.0, p1.0, p2.0, p3.0), axis(p0.1, p1.1, p2.1, p3.1)));
+        }
+        curve.push(p2);
+        let clear = curve.windows(2).all(|segment| {
+            obstacles
+                .iter()
+                .all(|obstacle| segment_distance(segment[0], segment[1], *obstacle) >= radius)
+        });
+        if clear {
+            smoothed.extend_from_slice(&curve[..curve.len() - 1]);
+        } else {
+            smoothed.push(p1);
+        }
+    }
+    smoothed.push(*points.last().unwrap());
+    smoothed
+}
+
+/// Where `point` projects on the line going through `a` and `b`, 0 at `a` and 1 at `b`.
+fn projection(a: (f32, f32), b: (f32, f32), point: (f32, f32)) -> f32 {
+    let (dx, dy) = (b.0 - a.0, b.1 - a.1);
+    let length_squared = dx * dx + dy * dy;
+    if length_squared == 0f32 {
+        return 0f32;
+    }
+    ((point.0 - a.0) * dx + (point.1 - a.1) * dy) / length_squared
+}
+
+/// Distance from `point` to the closest point of the segment from `a` to `b`.
+fn segment_distance(a: (f32, f32), b: (f32, f32), point: (f32, f32)) -> f32 {
+    let closest = lerp(a, b, projection(a, b, point).clamp(0f32, 1f32));
+    distance(closest, point)
+}
+
+fn lerp(a: (f32, f32), b: (f32, f32), t: f32) -> (f32, f32) {
+    (a.0 + (b.0 - a.0) * t, a.1 + (b.1 - a.1) * t)
+}
+
+fn distance(a: (f32, f32), b: (f32, f32)) -> f32 {
+    poisson::distance_squared(&a, &b).sqrt()
+}
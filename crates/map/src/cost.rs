@@ -0,0 +1,38 @@
+//! Traversal costs, shared by pathfinding and unit movement so estimated and actual travel
+//! times agree.
+
+use crate::{Map, RoomId};
+
+pub fn edge_length<T>(map: &Map<T>, from: RoomId, to: RoomId) -> Option<f32> {
+    let (from, to) = (map.rooms.get(&from)?, map.rooms.get(&to)?);
+    Some(poisson::distance_squared(&from.position, &to.position).sqrt())
+}
+
+/// Seconds to go from `from` to `to` at `speed`: length × terrain / speed, plus the door time.
+///
+/// Rooms don't need to be connected, the connection data is used when they are.
+pub fn travel_time<T>(map: &Map<T>, from: RoomId, to: RoomId, speed: f32) -> Option<f32> {
+    let length = edge_length(map, from, to)?;
+    Some(travel_time_over(map, from, to, length, speed))
+}
+
+/// Same as [`travel_time`] for a connection which doesn't go straight, like a routed corridor.
+pub fn travel_time_over<T>(map: &Map<T>, from: RoomId, to: RoomId, length: f32, speed: f32) -> f32 {
+    let data = map.edge_data(from, to);
+    length * data.terrain / speed + data.door_time
+}
+
+/// Seconds to follow every step of `path` at `speed`.
+pub fn path_travel_time<T>(map: &Map<T>, path: &[RoomId], speed: f32) -> Option<f32> {
+    path.windows(2)
+        .map(|step| travel_time(map, step[0], step[1], speed))
+        .sum()
+}
+
+/// Lowest terrain multiplier of the map, keeps A* estimates below actual travel times.
+pub(crate) fn min_terrain<T>(map: &Map<T>) -> f32 {
+    map.iter_edge_data()
+        .map(|(_, data)| data.terrain)
+        .fold(1f32, f32::min)
+        .max(0f32)
+}
@@ -0,0 +1,212 @@
+use std::{
+    cmp::Ordering,
+    collections::HashMap,
+    hash::{Hash, Hasher},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{metrics, Map, RoomId};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FingerprintConfig {
+    /// Weisfeiler-Lehman refinements, each one looks one hop further around rooms.
+    pub iterations: usize,
+    /// Positions are rounded to cells of this size for the geometric hash, `None` skips it.
+    pub cell_size: Option<f32>,
+}
+
+impl Default for FingerprintConfig {
+    fn default() -> Self {
+        Self {
+            iterations: 3,
+            cell_size: Some(20f32),
+        }
+    }
+}
+
+/// Hashes identifying a map, stable across runs and platforms.
+///
+/// `topology` ignores room ids, positions and data: isomorphic maps share it. Different maps
+/// sharing it is possible but rare, Weisfeiler-Lehman can't tell some regular graphs apart.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Fingerprint {
+    pub topology: u64,
+    /// Rounded room positions and edges, relative to the lowest coordinates so translating a map
+    /// keeps it.
+    pub geometry: Option<u64>,
+    /// Weisfeiler-Lehman labels of every room at every iteration, sorted.
+    pub features: Vec<u64>,
+}
+
+impl Fingerprint {
+    pub fn compute<T>(map: &Map<T>, config: &FingerprintConfig) -> Self {
+        let features = wl_features(map, config.iterations);
+        Self {
+            topology: stable_hash(&Words(&features)),
+            geometry: config
+                .cell_size
+                .map(|cell_size| geometric_hash(map, cell_size)),
+            features,
+        }
+    }
+
+    /// Same topology and, when both have one, same geometry.
+    pub fn is_duplicate_of(&self, other: &Fingerprint) -> bool {
+        self.topology == other.topology
+            && match (self.geometry, other.geometry) {
+                (Some(a), Some(b)) => a == b,
+                _ => true,
+            }
+    }
+
+    /// Share of Weisfeiler-Lehman labels in common, from 0 to 1 for identical topologies.
+    pub fn similarity(&self, other: &Fingerprint) -> f32 {
+        let (mut common, mut i, mut j) = (0usize, 0usize, 0usize);
+        while i < self.features.len() && j < other.features.len() {
+            match self.features[i].cmp(&other.features[j]) {
+                Ordering::Less => i += 1,
+                Ordering::Greater => j += 1,
+                Ordering::Equal => {
+                    common += 1;
+                    i += 1;
+                    j += 1;
+                }
+            }
+        }
+        let total = self.features.len() + other.features.len() - common;
+        if total == 0 {
+            return 1f32;
+        }
+        common as f32 / total as f32
+    }
+}
+
+/// Weisfeiler-Lehman hash of the undirected topology.
+pub fn topology_hash<T>(map: &Map<T>, iterations: usize) -> u64 {
+    stable_hash(&Words(&wl_features(map, iterations)))
+}
+
+/// Similarity of two maps' topologies, see [`Fingerprint::similarity`].
+pub fn similarity<T, U>(a: &Map<T>, b: &Map<U>, iterations: usize) -> f32 {
+    let config = FingerprintConfig {
+        iterations,
+        cell_size: None,
+    };
+    Fingerprint::compute(a, &config).similarity(&Fingerprint::compute(b, &config))
+}
+
+/// Hash of the rounded positions and edges, see [`Fingerprint::geometry`].
+pub fn geometric_hash<T>(map: &Map<T>, cell_size: f32) -> u64 {
+    let origin = map
+        .rooms
+        .values()
+        .map(|r| r.position)
+        .fold((f32::INFINITY, f32::INFINITY), |origin, (x, y)| {
+            (origin.0.min(x), origin.1.min(y))
+        });
+    let cell = |id: &RoomId| {
+        let (x, y) = map.rooms[id].position;
+        (
+            ((x - origin.0) / cell_size).round() as i64,
+            ((y - origin.1) / cell_size).round() as i64,
+        )
+    };
+    let mut cells: Vec<(i64, i64)> = map.rooms.keys().map(cell).collect();
+    cells.sort();
+    let mut edges: Vec<((i64, i64), (i64, i64))> = map
+        .edges()
+        .iter()
+        .map(|(a, b)| {
+            let (a, b) = (cell(a), cell(b));
+            (a.min(b), a.max(b))
+        })
+        .collect();
+    edges.sort();
+    stable_hash(&(cells, edges))
+}
+
+/// Labels start as degrees, then each refinement hashes a room's label with its neighbours'.
+fn wl_features<T>(map: &Map<T>, iterations: usize) -> Vec<u64> {
+    let adjacency = metrics::undirected_adjacency(map);
+    let mut labels: HashMap<RoomId, u64> = adjacency
+        .iter()
+        .map(|(id, neighbours)| (*id, stable_hash(&(neighbours.len() as u64))))
+        .collect();
+    let mut features: Vec<u64> = labels.values().copied().collect();
+    for iteration in 0..iterations {
+        labels = adjacency
+            .iter()
+            .map(|(id, neighbours)| {
+                let mut around: Vec<u64> = neighbours.iter().map(|n| labels[n]).collect();
+                around.sort();
+                (
+                    *id,
+                    stable_hash(&(iteration as u64, labels[id], Words(&around))),
+                )
+            })
+            .collect();
+        features.extend(labels.values());
+    }
+    // Duplicates are kept, similarity compares multisets.
+    features.sort();
+    features
+}
+
+/// `u64`s hashed one by one: slices of integers are otherwise written as a single block of
+/// native endian bytes, bypassing [`Fnv64::write_u64`].
+struct Words<'a>(&'a [u64]);
+
+impl Hash for Words<'_> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        state.write_usize(self.0.len());
+        for word in self.0 {
+            state.write_u64(*word);
+        }
+    }
+}
+
+fn stable_hash(value: &impl Hash) -> u64 {
+    let mut hasher = Fnv64::default();
+    value.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// FNV-1a: `DefaultHasher` may change between Rust releases, stored fingerprints must not.
+struct Fnv64(u64);
+
+impl Default for Fnv64 {
+    fn default() -> Self {
+        Self(0xcbf2_9ce4_8422_2325)
+    }
+}
+
+impl Hasher for Fnv64 {
+    fn finish(&self) -> u64 {
+        self.0
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.0 ^= *byte as u64;
+            self.0 = self.0.wrapping_mul(0x0100_0000_01b3);
+        }
+    }
+
+    // Integers are written little endian and `usize` as `u64`, whatever the platform.
+    fn write_u16(&mut self, value: u16) {
+        self.write(&value.to_le_bytes());
+    }
+
+    fn write_u32(&mut self, value: u32) {
+        self.write(&value.to_le_bytes());
+    }
+
+    fn write_u64(&mut self, value: u64) {
+        self.write(&value.to_le_bytes());
+    }
+
+    fn write_usize(&mut self, value: usize) {
+        self.write_u64(value as u64);
+    }
+}
@@ -0,0 +1,204 @@
+//! Fog of war: what each faction discovered of a map, and what it currently sees.
+//!
+//! Observers see the rooms a few connections away from theirs, and the connections between
+//! those rooms. Whatever was seen once stays discovered.
+
+use std::collections::{btree_map::Entry, BTreeMap, BTreeSet, VecDeque};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{Map, RoomId};
+
+#[derive(
+    PartialOrd, Ord, PartialEq, Eq, Hash, Default, Clone, Copy, Debug, Serialize, Deserialize,
+)]
+pub struct Faction(pub u32);
+
+/// Rooms and connections known by a faction.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Knowledge {
+    discovered_rooms: BTreeSet<RoomId>,
+    discovered_edges: BTreeSet<(RoomId, RoomId)>,
+    visible_rooms: BTreeSet<RoomId>,
+    visible_edges: BTreeSet<(RoomId, RoomId)>,
+}
+
+impl Knowledge {
+    pub fn is_discovered(&self, room: RoomId) -> bool {
+        self.discovered_rooms.contains(&room)
+    }
+
+    pub fn is_visible(&self, room: RoomId) -> bool {
+        self.visible_rooms.contains(&room)
+    }
+
+    pub fn is_edge_discovered(&self, from: RoomId, to: RoomId) -> bool {
+        self.discovered_edges.contains(&(from, to))
+    }
+
+    pub fn is_edge_visible(&self, from: RoomId, to: RoomId) -> bool {
+        self.visible_edges.contains(&(from, to))
+    }
+
+    pub fn discovered_rooms(&self) -> impl Iterator<Item = RoomId> + '_ {
+        self.discovered_rooms.iter().copied()
+    }
+
+    pub fn visible_rooms(&self) -> impl Iterator<Item = RoomId> + '_ {
+        self.visible_rooms.iter().copied()
+    }
+
+    pub fn discovered_edges(&self) -> impl Iterator<Item = (RoomId, RoomId)> + '_ {
+        self.discovered_edges.iter().copied()
+    }
+
+    /// Discovered connections leaving `room`, sorted.
+    pub fn known_connections(&self, room: RoomId) -> impl Iterator<Item = RoomId> + '_ {
+        self.discovered_edges
+            .range((room, RoomId::default())..)
+            .take_while(move |(from, _)| *from == room)
+            .map(|(_, to)| *to)
+    }
+
+    /// Sees again from the rooms of `observers`, up to `range` connections away.
+    ///
+    /// Rooms removed from the map are forgotten, as are removed connections leaving a room in
+    /// sight.
+    pub fn update<T>(
+        &mut self,
+        map: &Map<T>,
+        observers: impl IntoIterator<Item = RoomId>,
+        range: usize,
+    ) {
+        self.discovered_rooms
+            .retain(|id| map.rooms.contains_key(id));
+        self.discovered_edges
+            .retain(|(from, to)| map.rooms.contains_key(from) && map.rooms.contains_key(to));
+
+        self.visible_rooms = in_range(map, observers, range);
+        self.visible_edges = self
+            .visible_rooms
+            .iter()
+            .flat_map(|from| {
+                map.rooms[from]
+                    .connections
+                    .iter()
+                    .filter(|to| self.visible_rooms.contains(to))
+                    .map(move |to| (*from, *to))
+            })
+            .collect();
+
+        // Corridors leaving a room in sight are seen, even when where they led is not.
+        let visible_rooms = &self.visible_rooms;
+        self.discovered_edges.retain(|(from, to)| {
+            !visible_rooms.contains(from) || map.rooms[from].connections.contains(to)
+        });
+        self.discovered_rooms.extend(visible_rooms.iter().copied());
+        self.discovered_edges
+            .extend(self.visible_edges.iter().copied());
+    }
+
+    /// Shortest path in hops going only through discovered connections, ends included.
+    pub fn known_path(&self, from: RoomId, to: RoomId) -> Option<Vec<RoomId>> {
+        if !self.is_discovered(from) || !self.is_discovered(to) {
+            return None;
+        }
+        let mut previous = BTreeMap::from([(from, from)]);
+        let mut queue = VecDeque::from([from]);
+        while let Some(current) = queue.pop_front() {
+            if current == to {
+                let mut path = vec![to];
+                while *path.last().unwrap() != from {
+                    path.push(previous[path.last().unwrap()]);
+                }
+                path.reverse();
+                return Some(path);
+            }
+            for next in self.known_connections(current) {
+                if let Entry::Vacant(entry) = previous.entry(next) {
+                    entry.insert(current);
+                    queue.push_back(next);
+                }
+            }
+        }
+        None
+    }
+}
+
+/// Knowledge of every faction of a map.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FogOfWar {
+    /// Connections an observer sees through, 0 to only see its own room.
+    pub vision_range: usize,
+    factions: BTreeMap<Faction, Knowledge>,
+}
+
+impl Default for FogOfWar {
+    fn default() -> Self {
+        Self::new(1)
+    }
+}
+
+impl FogOfWar {
+    pub fn new(vision_range: usize) -> Self {
+        Self {
+            vision_range,
+            factions: BTreeMap::new(),
+        }
+    }
+
+    /// Knowledge of a faction, `None` until it was updated once.
+    pub fn knowledge(&self, faction: Faction) -> Option<&Knowledge> {
+        self.factions.get(&faction)
+    }
+
+    pub fn factions(&self) -> impl Iterator<Item = (Faction, &Knowledge)> {
+        self.factions
+            .iter()
+            .map(|(faction, knowledge)| (*faction, knowledge))
+    }
+
+    /// Updates what `faction` sees from the rooms of its `observers`.
+    pub fn update<T>(
+        &mut self,
+        map: &Map<T>,
+        faction: Faction,
+        observers: impl IntoIterator<Item = RoomId>,
+    ) {
+        let range = self.vision_range;
+        self.factions
+            .entry(faction)
+            .or_default()
+            .update(map, observers, range);
+    }
+
+    pub fn forget(&mut self, faction: Faction) -> Option<Knowledge> {
+        self.factions.remove(&faction)
+    }
+}
+
+/// Rooms at most `range` connections away from one of `origins`.
+fn in_range<T>(
+    map: &Map<T>,
+    origins: impl IntoIterator<Item = RoomId>,
+    range: usize,
+) -> BTreeSet<RoomId> {
+    let mut seen = BTreeSet::new();
+    let mut queue = VecDeque::new();
+    for origin in origins {
+        if map.rooms.contains_key(&origin) && seen.insert(origin) {
+            queue.push_back((origin, 0));
+        }
+    }
+    while let Some((current, hops)) = queue.pop_front() {
+        if hops == range {
+            continue;
+        }
+        for next in map.rooms[&current].connections.iter() {
+            if map.rooms.contains_key(next) && seen.insert(*next) {
+                queue.push_back((*next, hops + 1));
+            }
+        }
+    }
+    seen
+}
@@ -0,0 +1,129 @@
+use std::collections::HashMap;
+
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::{Map, RoomId};
+
+/// Parameters of the distance-based growth generator.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GeneratorConfig {
+    /// Number of growth steps; a step may fail to place a room when the map is cluttered.
+    pub rooms: usize,
+    /// Rooms tried as growth origin during one step.
+    pub attempts_per_room: u32,
+    /// Poisson attempts around the chosen origin.
+    pub nb_tries: u32,
+    /// New rooms are connected to every room closer than this.
+    pub connect_distance: f32,
+    /// Half size of the square where the first room is placed.
+    pub spawn_extent: f32,
+}
+
+impl Default for GeneratorConfig {
+    fn default() -> Self {
+        Self {
+            rooms: 25,
+            attempts_per_room: 5,
+            nb_tries: 10,
+            connect_distance: 50f32,
+            spawn_extent: 30f32,
+        }
+    }
+}
+
+#[derive(Default)]
+struct RoomClutter {
+    pub nb_gen_tries: u8,
+}
+
+/// Grows a map one room at a time, avoiding rooms which repeatedly failed to expand.
+#[derive(Default)]
+pub struct Generator {
+    pub config: GeneratorConfig,
+    clutters: HashMap<RoomId, RoomClutter>,
+}
+
+impl Generator {
+    /// Identifies maps made by this generator in save files.
+    pub const NAME: &'static str = "distance_growth";
+
+    pub fn new(config: GeneratorConfig) -> Self {
+        Self {
+            config,
+            clutters: HashMap::new(),
+        }
+    }
+
+    /// Runs `config.rooms` growth steps on `map`.
+    pub fn generate<T: Clone>(&mut self, map: &mut Map<T>, data: T, rng: &mut impl Rng) {
+        for _ in 0..self.config.rooms {
+            self.grow(map, data.clone(), rng);
+        }
+    }
+
+    /// One growth step: creates the first room of an empty map, or expands from a random room.
+    pub fn grow<T: Clone>(
+        &mut self,
+        map: &mut Map<T>,
+        data: T,
+        rng: &mut impl Rng,
+    ) -> Option<RoomId> {
+        for _ in 0..self.config.attempts_per_room {
+            let mut filtered_rooms: Vec<RoomId> = map
+                .sorted_ids()
+                .into_iter()
+                .filter(|id| match self.clutters.get(id) {
+                    Some(clutter) => clutter.nb_gen_tries <= 1,
+                    None => true,
+                })
+                .collect();
+            if filtered_rooms.is_empty() {
+                self.clutters.clear();
+                filtered_rooms = map.sorted_ids();
+            }
+            if filtered_rooms.is_empty() {
+                let extent = self.config.spawn_extent;
+                return Some(map.create_raw(
+                    data,
+                    (
+                        rng.gen_range(-1f32..=1f32) * extent,
+                        rng.gen_range(-1f32..=1f32) * extent,
+                    ),
+                    vec![],
+                ));
+            }
+            let from_room = filtered_rooms[rng.gen_range(0..filtered_rooms.len())];
+            match map.add(from_room, data.clone(), rng, self.config.nb_tries) {
+                Ok(room_id) => {
+                    self.connect_neighbours(map, room_id);
+                    return Some(room_id);
+                }
+                Err(_) => {
+                    self.clutters.entry(from_room).or_default().nb_gen_tries += 1;
+                }
+            }
+        }
+        None
+    }
+
+    fn connect_neighbours<T>(&self, map: &mut Map<T>, room_id: RoomId) {
+        let pos_new = map.rooms[&room_id].position;
+        let max_distance_squared = self.config.connect_distance * self.config.connect_distance;
+        let to_connect: Vec<RoomId> = map
+            .sorted_ids()
+            .into_iter()
+            .filter(|id| {
+                *id != room_id
+                    && !map.rooms[&room_id].connections.contains(id)
+                    && poisson::distance_squared(&pos_new, &map.rooms[id].position)
+                        < max_distance_squared
+            })
+            .collect();
+        for other in to_connect {
+            // Both rooms exist, connecting can't fail.
+            let _ = map.connect(other, room_id);
+            let _ = map.connect(room_id, other);
+        }
+    }
+}
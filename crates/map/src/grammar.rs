@@ -0,0 +1,326 @@
+//! Graph rewriting: rules replace labeled subgraphs of a map, the room data being the labels.
+//!
+//! Rules are meant to be authored in RON, for example:
+//!
+//! ```ron
+//! (
+//!     rules: [
+//!         (
+//!             name: "side_room",
+//!             lhs: (nodes: [Corridor], edges: []),
+//!             rhs: (
+//!                 nodes: [
+//!                     (label: Corridor, keep: Some(0)),
+//!                     (label: SideRoom),
+//!                     (label: Corridor),
+//!                 ],
+//!                 edges: [(0, 1), (1, 2)],
+//!             ),
+//!         ),
+//!     ],
+//! )
+//! ```
+
+use std::collections::{HashMap, HashSet};
+
+use poisson::Poisson;
+use rand::{seq::SliceRandom, Rng};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::{get_position_around, metrics, Map, RoomId};
+
+/// Subgraph to look for: rooms with these labels, linked by these edges.
+///
+/// Edges are undirected and refer to indices in `nodes`, extra edges in the map are allowed.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Pattern<T> {
+    pub nodes: Vec<T>,
+    #[serde(default)]
+    pub edges: Vec<(usize, usize)>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReplacementNode<T> {
+    pub label: T,
+    /// Index of the matched node this one replaces, keeping its room, position and connections
+    /// to rooms outside of the match. Matched nodes nobody keeps are removed.
+    #[serde(default)]
+    pub keep: Option<usize>,
+}
+
+/// Subgraph replacing a match, edges are created both ways.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Replacement<T> {
+    pub nodes: Vec<ReplacementNode<T>>,
+    #[serde(default)]
+    pub edges: Vec<(usize, usize)>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Rule<T> {
+    pub name: String,
+    pub lhs: Pattern<T>,
+    pub rhs: Replacement<T>,
+    /// Relative chance to pick this rule among the applicable ones.
+    #[serde(default = "default_weight")]
+    pub weight: u32,
+}
+
+fn default_weight() -> u32 {
+    1
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Grammar<T> {
+    pub rules: Vec<Rule<T>>,
+}
+
+#[derive(Error, Debug)]
+pub enum GrammarError {
+    #[error("Could not parse grammar: {0}")]
+    Parse(#[from] ron::Error),
+    #[error("Rule `{rule}` refers to node {index} which does not exist")]
+    InvalidNodeIndex { rule: String, index: usize },
+    #[error("Rule `{rule}` keeps node {index} more than once")]
+    NodeKeptTwice { rule: String, index: usize },
+}
+
+/// Rooms matched by the nodes of a rule's `lhs`, in node order.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Match {
+    pub rule: usize,
+    pub rooms: Vec<RoomId>,
+}
+
+impl<T: DeserializeOwned> Grammar<T> {
+    pub fn from_ron(source: &str) -> Result<Self, GrammarError> {
+        let grammar: Self = ron::from_str(source)?;
+        grammar.validate()?;
+        Ok(grammar)
+    }
+}
+
+impl<T> Grammar<T> {
+    fn validate(&self) -> Result<(), GrammarError> {
+        for rule in self.rules.iter() {
+            let invalid = |index: usize| GrammarError::InvalidNodeIndex {
+                rule: rule.name.clone(),
+                index,
+            };
+            let lhs_len = rule.lhs.nodes.len();
+            let rhs_len = rule.rhs.nodes.len();
+            for (a, b) in rule.lhs.edges.iter() {
+                if *a >= lhs_len || *b >= lhs_len {
+                    return Err(invalid((*a).max(*b)));
+                }
+            }
+            for (a, b) in rule.rhs.edges.iter() {
+                if *a >= rhs_len || *b >= rhs_len {
+                    return Err(invalid((*a).max(*b)));
+                }
+            }
+            let mut kept = HashSet::new();
+            for keep in rule.rhs.nodes.iter().filter_map(|n| n.keep) {
+                if keep >= lhs_len {
+                    return Err(invalid(keep));
+                }
+                if !kept.insert(keep) {
+                    return Err(GrammarError::NodeKeptTwice {
+                        rule: rule.name.clone(),
+                        index: keep,
+                    });
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<T: PartialEq + Clone> Grammar<T> {
+    /// Every match of every rule, in a stable order.
+    pub fn find_matches(&self, map: &Map<T>) -> Vec<Match> {
+        let adjacency = metrics::undirected_adjacency(map);
+        let mut matches = vec![];
+        for (index, rule) in self.rules.iter().enumerate() {
+            let mut rooms = vec![];
+            find_rule_matches(map, &adjacency, &rule.lhs, &mut rooms, &mut |rooms| {
+                matches.push(Match {
+                    rule: index,
+                    rooms: rooms.to_vec(),
+                })
+            });
+        }
+        matches
+    }
+
+    /// Applies up to `max_rewrites` random rewrites, returns how many were applied.
+    ///
+    /// The same seed and map always give the same result.
+    pub fn apply(&self, map: &mut Map<T>, rng: &mut impl Rng, max_rewrites: usize) -> usize {
+        for rewrites in 0..max_rewrites {
+            if self.apply_once(map, rng).is_none() {
+                return rewrites;
+            }
+        }
+        max_rewrites
+    }
+
+    /// Picks a rule by weight among the applicable ones, then one of its matches.
+    pub fn apply_once(&self, map: &mut Map<T>, rng: &mut impl Rng) -> Option<Match> {
+        let mut matches = self.find_matches(map);
+        while !matches.is_empty() {
+            let mut applicable: Vec<usize> = matches.iter().map(|m| m.rule).collect();
+            applicable.dedup();
+            let rule = *applicable
+                .choose_weighted(rng, |rule| self.rules[*rule].weight)
+                .ok()?;
+            let rule_matches: Vec<&Match> = matches.iter().filter(|m| m.rule == rule).collect();
+            let chosen = (*rule_matches.choose(rng).unwrap()).clone();
+            if self.rewrite(map, &chosen, rng) {
+                return Some(chosen);
+            }
+            // No room to place the new nodes around this match.
+            matches.retain(|m| *m != chosen);
+        }
+        None
+    }
+
+    fn rewrite(&self, map: &mut Map<T>, matched: &Match, rng: &mut impl Rng) -> bool {
+        let rule = &self.rules[matched.rule];
+        let kept: HashMap<usize, usize> = rule
+            .rhs
+            .nodes
+            .iter()
+            .enumerate()
+            .filter_map(|(rhs, node)| node.keep.map(|lhs| (rhs, lhs)))
+            .collect();
+        let removed: Vec<RoomId> = (0..rule.lhs.nodes.len())
+            .filter(|lhs| !kept.values().any(|k| k == lhs))
+            .map(|lhs| matched.rooms[lhs])
+            .collect();
+
+        // Plan positions before touching the map, so a failed placement leaves it untouched.
+        let mut positions: Vec<(f32, f32)> = map
+            .rooms
+            .iter()
+            .filter(|(id, _)| !removed.contains(id))
+            .map(|(_, room)| room.position)
+            .collect();
+        let mut planned: HashMap<usize, (f32, f32)> = kept
+            .iter()
+            .map(|(rhs, lhs)| (*rhs, map.rooms[&matched.rooms[*lhs]].position))
+            .collect();
+        let centroid = centroid(matched.rooms.iter().map(|id| map.rooms[id].position));
+        for rhs in 0..rule.rhs.nodes.len() {
+            if planned.contains_key(&rhs) {
+                continue;
+            }
+            let mut ref_points: Vec<(f32, f32)> = rule
+                .rhs
+                .edges
+                .iter()
+                .filter_map(|(a, b)| match (*a == rhs, *b == rhs) {
+                    (true, false) => planned.get(b).copied(),
+                    (false, true) => planned.get(a).copied(),
+                    _ => None,
+                })
+                .collect();
+            ref_points.push(centroid);
+            let position =
+                match get_position_around(10, Poisson::new(), positions.clone(), ref_points, rng) {
+                    Some(position) => position,
+                    None => return false,
+                };
+            positions.push(position);
+            planned.insert(rhs, position);
+        }
+
+        for id in removed {
+            map.remove(id);
+        }
+        for (a, b) in rule.lhs.edges.iter() {
+            let (a, b) = (matched.rooms[*a], matched.rooms[*b]);
+            map.disconnect(a, b);
+            map.disconnect(b, a);
+        }
+        let mut rooms: Vec<RoomId> = vec![];
+        for (rhs, node) in rule.rhs.nodes.iter().enumerate() {
+            let room = match node.keep {
+                Some(lhs) => {
+                    let room = matched.rooms[lhs];
+                    map.set_data(room, node.label.clone());
+                    room
+                }
+                None => map.create_raw(node.label.clone(), planned[&rhs], vec![]),
+            };
+            rooms.push(room);
+        }
+        for (a, b) in rule.rhs.edges.iter() {
+            let (a, b) = (rooms[*a], rooms[*b]);
+            let _ = map.connect(a, b);
+            let _ = map.connect(b, a);
+        }
+        true
+    }
+}
+
+fn find_rule_matches<T: PartialEq>(
+    map: &Map<T>,
+    adjacency: &HashMap<RoomId, Vec<RoomId>>,
+    pattern: &Pattern<T>,
+    rooms: &mut Vec<RoomId>,
+    found: &mut impl FnMut(&[RoomId]),
+) {
+    let index = rooms.len();
+    if index == pattern.nodes.len() {
+        found(rooms);
+        return;
+    }
+    // Rooms linked to an already matched node, when there is one, are the only candidates.
+    let linked = pattern.edges.iter().find_map(|(a, b)| {
+        if *a == index && *b < index {
+            Some(rooms[*b])
+        } else if *b == index && *a < index {
+            Some(rooms[*a])
+        } else {
+            None
+        }
+    });
+    let mut candidates = match linked {
+        Some(room) => adjacency[&room].clone(),
+        None => map.sorted_ids(),
+    };
+    candidates.sort();
+    for candidate in candidates {
+        if rooms.contains(&candidate) || map.rooms[&candidate].data != pattern.nodes[index] {
+            continue;
+        }
+        let edges_match = pattern.edges.iter().all(|(a, b)| {
+            let other = match (*a == index, *b == index) {
+                (true, false) if *b < index => rooms[*b],
+                (false, true) if *a < index => rooms[*a],
+                _ => return true,
+            };
+            adjacency[&candidate].contains(&other)
+        });
+        if !edges_match {
+            continue;
+        }
+        rooms.push(candidate);
+        find_rule_matches(map, adjacency, pattern, rooms, found);
+        rooms.pop();
+    }
+}
+
+fn centroid(positions: impl Iterator<Item = (f32, f32)>) -> (f32, f32) {
+    let (mut sum, mut count) = ((0f32, 0f32), 0f32);
+    for (x, y) in positions {
+        sum = (sum.0 + x, sum.1 + y);
+        count += 1f32;
+    }
+    if count == 0f32 {
+        return sum;
+    }
+    (sum.0 / count, sum.1 / count)
+}
@@ -0,0 +1,197 @@
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::{Map, RoomId, ROOM_SPACING};
+
+/// Rooms further apart than this many ideal distances don't repulse each other, from the grid
+/// variant of Fruchterman-Reingold: without it, maps keep inflating.
+const REPULSION_RANGE: f32 = 2f32;
+
+/// Passes of the minimum distance projection after each step, before spreading the whole layout
+/// instead.
+const SPACING_PASSES: usize = 50;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LayoutConfig {
+    pub iterations: usize,
+    /// Distance connected rooms settle at, Fruchterman-Reingold's `k`.
+    pub ideal_distance: f32,
+    /// Maximum move of a room during the first step, decreasing linearly to 0.
+    pub initial_temperature: f32,
+    /// Rooms are never left closer than this, defaults to the poisson spacing.
+    pub min_distance: f32,
+}
+
+impl Default for LayoutConfig {
+    fn default() -> Self {
+        Self {
+            iterations: 100,
+            ideal_distance: 45f32,
+            initial_temperature: 20f32,
+            min_distance: ROOM_SPACING,
+        }
+    }
+}
+
+/// Spring-electrical relaxation of room positions, run step by step so it can be animated.
+pub struct ForceLayout {
+    pub config: LayoutConfig,
+    iteration: usize,
+}
+
+impl ForceLayout {
+    pub fn new(config: LayoutConfig) -> Self {
+        Self {
+            config,
+            iteration: 0,
+        }
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.iteration >= self.config.iterations
+    }
+
+    /// Runs the remaining iterations.
+    pub fn run<T>(&mut self, map: &mut Map<T>, rng: &mut impl Rng) {
+        while self.step(map, rng) {}
+    }
+
+    /// Moves every room once, returns `false` when the layout is done.
+    ///
+    /// `rng` only breaks ties between rooms sharing a position.
+    pub fn step<T>(&mut self, map: &mut Map<T>, rng: &mut impl Rng) -> bool {
+        if self.is_done() {
+            return false;
+        }
+        let ids = map.sorted_ids();
+        let mut positions: Vec<(f32, f32)> = ids.iter().map(|id| map.rooms[id].position).collect();
+        let k = self.config.ideal_distance;
+        let temperature = self.config.initial_temperature
+            * (1f32 - self.iteration as f32 / self.config.iterations as f32);
+
+        let mut displacements = vec![(0f32, 0f32); ids.len()];
+        for i in 0..ids.len() {
+            for j in (i + 1)..ids.len() {
+                let (dx, dy, distance) = delta(positions[i], positions[j], rng);
+                if distance > REPULSION_RANGE * k {
+                    continue;
+                }
+                let force = k * k / distance;
+                let (fx, fy) = (dx / distance * force, dy / distance * force);
+                displacements[i] = (displacements[i].0 + fx, displacements[i].1 + fy);
+                displacements[j] = (displacements[j].0 - fx, displacements[j].1 - fy);
+            }
+        }
+        for (a, b) in map.edges() {
+            let (i, j) = (index_of(&ids, a), index_of(&ids, b));
+            let (i, j) = match (i, j) {
+                (Some(i), Some(j)) => (i, j),
+                _ => continue,
+            };
+            let (dx, dy, distance) = delta(positions[i], positions[j], rng);
+            let force = distance * distance / k;
+            let (fx, fy) = (dx / distance * force, dy / distance * force);
+            displacements[i] = (displacements[i].0 - fx, displacements[i].1 - fy);
+            displacements[j] = (displacements[j].0 + fx, displacements[j].1 + fy);
+        }
+        for (position, (dx, dy)) in positions.iter_mut().zip(displacements) {
+            let length = (dx * dx + dy * dy).sqrt();
+            if length > 0f32 {
+                let applied = length.min(temperature);
+                *position = (
+                    position.0 + dx / length * applied,
+                    position.1 + dy / length * applied,
+                );
+            }
+        }
+        self.keep_min_distance(&mut positions, rng);
+
+        for (id, position) in ids.iter().zip(positions) {
+            map.set_position(*id, position);
+        }
+        self.iteration += 1;
+        !self.is_done()
+    }
+
+    /// Pushes apart rooms closer than `min_distance`, each by half the missing distance.
+    fn keep_min_distance(&self, positions: &mut [(f32, f32)], rng: &mut impl Rng) {
+        let min_distance = self.config.min_distance;
+        for _ in 0..SPACING_PASSES {
+            let mut moved = false;
+            for i in 0..positions.len() {
+                for j in (i + 1)..positions.len() {
+                    let (dx, dy, distance) = delta(positions[i], positions[j], rng);
+                    if distance >= min_distance {
+                        continue;
+                    }
+                    // A little over half, so float rounding doesn't leave rooms just too close.
+                    let push = (min_distance - distance) * 0.501f32;
+                    let (px, py) = (dx / distance * push, dy / distance * push);
+                    positions[i] = (positions[i].0 + px, positions[i].1 + py);
+                    positions[j] = (positions[j].0 - px, positions[j].1 - py);
+                    moved = true;
+                }
+            }
+            if !moved {
+                return;
+            }
+        }
+        // Pushes can keep bouncing rooms of dense clusters against each other, scaling the whole
+        // layout always makes room.
+        spread(positions, min_distance, rng);
+    }
+}
+
+/// Scales positions from their center until no rooms are closer than `min_distance`.
+fn spread(positions: &mut [(f32, f32)], min_distance: f32, rng: &mut impl Rng) {
+    let mut closest = f32::INFINITY;
+    for i in 0..positions.len() {
+        for j in (i + 1)..positions.len() {
+            let (dx, dy) = (
+                positions[i].0 - positions[j].0,
+                positions[i].1 - positions[j].1,
+            );
+            if dx * dx + dy * dy < f32::EPSILON {
+                // Scaling doesn't part rooms sharing a position.
+                let (dx, dy, _) = delta(positions[i], positions[j], rng);
+                positions[j] = (positions[j].0 - dx, positions[j].1 - dy);
+            }
+            closest = closest.min(distance(positions[i], positions[j]));
+        }
+    }
+    if closest >= min_distance {
+        return;
+    }
+    let count = positions.len() as f32;
+    let center = (
+        positions.iter().map(|p| p.0).sum::<f32>() / count,
+        positions.iter().map(|p| p.1).sum::<f32>() / count,
+    );
+    // A little over, for the same rounding reason as the pushes.
+    let scale = min_distance / closest * 1.001f32;
+    for position in positions.iter_mut() {
+        *position = (
+            center.0 + (position.0 - center.0) * scale,
+            center.1 + (position.1 - center.1) * scale,
+        );
+    }
+}
+
+fn distance(a: (f32, f32), b: (f32, f32)) -> f32 {
+    ((a.0 - b.0).powi(2) + (a.1 - b.1).powi(2)).sqrt()
+}
+
+/// Vector from `b` to `a` and its length, never 0.
+fn delta(a: (f32, f32), b: (f32, f32), rng: &mut impl Rng) -> (f32, f32, f32) {
+    let (mut dx, mut dy) = (a.0 - b.0, a.1 - b.1);
+    let mut distance = (dx * dx + dy * dy).sqrt();
+    if distance < f32::EPSILON {
+        let angle = rng.gen_range(0f32..std::f32::consts::TAU);
+        (dx, dy, distance) = (angle.cos() * 0.01f32, angle.sin() * 0.01f32, 0.01f32);
+    }
+    (dx, dy, distance)
+}
+
+fn index_of(ids: &[RoomId], id: RoomId) -> Option<usize> {
+    ids.binary_search(&id).ok()
+}
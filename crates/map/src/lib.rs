@@ -1,34 +1,181 @@
-use std::collections::HashMap;
+pub mod chunks;
+pub mod corridors;
+pub mod cost;
+pub mod fingerprint;
+pub mod fog;
+pub mod generator;
+pub mod grammar;
+pub mod layout;
+pub mod lock_and_key;
+pub mod metrics;
+pub mod pathfinding;
+pub mod placement;
+pub mod raster;
+pub mod save;
+pub mod undo;
+pub mod voronoi;
+pub mod zones;
+
+use std::collections::{BTreeMap, HashMap, VecDeque};
 
 use poisson::Poisson;
 use rand::Rng;
+use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
-#[derive(PartialOrd, PartialEq, Eq, Hash, Default, Clone, Copy, Debug)]
+/// Minimum distance kept between two rooms placed by [`Map::add`].
+pub const ROOM_SPACING: f32 = 40f32;
+
+#[derive(
+    PartialOrd, Ord, PartialEq, Eq, Hash, Default, Clone, Copy, Debug, Serialize, Deserialize,
+)]
 pub struct RoomId(usize);
 
-#[derive(Debug)]
+impl std::fmt::Display for RoomId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Room<T: Sized> {
     pub connections: Vec<RoomId>,
     pub position: (f32, f32),
     pub data: T,
 }
 
-#[derive(Debug, Default)]
+/// How hard a connection is to go through, see [`cost::travel_time`].
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct EdgeData {
+    /// Multiplies the connection length: 2 for mud, 0.5 for a conveyor belt.
+    #[serde(default = "default_terrain")]
+    pub terrain: f32,
+    /// Seconds spent opening a door before going through.
+    #[serde(default)]
+    pub door_time: f32,
+}
+
+fn default_terrain() -> f32 {
+    1f32
+}
+
+impl Default for EdgeData {
+    fn default() -> Self {
+        Self {
+            terrain: default_terrain(),
+            door_time: 0f32,
+        }
+    }
+}
+
+/// Change recorded by [`Map`] mutations once [`Map::record_events`] is on, drained by consumers
+/// with [`Map::drain_events`].
+///
+/// Moves and data changes of a room, or of a connection, are only recorded once until drained.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum MapEvent {
+    RoomAdded(RoomId),
+    /// Sent after the removal of every connection from or to the room.
+    RoomRemoved(RoomId),
+    EdgeAdded(RoomId, RoomId),
+    EdgeRemoved(RoomId, RoomId),
+    RoomMoved(RoomId),
+    DataChanged(RoomId),
+    EdgeDataChanged(RoomId, RoomId),
+}
+
+#[derive(Debug, Serialize, Deserialize)]
 pub struct Map<T: Sized> {
+    /// Serialized by id, so a map is always written the same.
+    #[serde(serialize_with = "serialize_sorted", bound(serialize = "T: Serialize"))]
     pub rooms: HashMap<RoomId, Room<T>>,
     room_id_provider: RoomId,
+    /// Data of one-way connections, connections without an entry use [`EdgeData::default`].
+    #[serde(
+        default,
+        skip_serializing_if = "BTreeMap::is_empty",
+        with = "edge_data_entries"
+    )]
+    edge_data: BTreeMap<(RoomId, RoomId), EdgeData>,
+    /// Changes since the last drain, writing to `rooms` directly doesn't record any.
+    #[serde(skip)]
+    events: Vec<MapEvent>,
+    #[serde(skip)]
+    recording: bool,
+}
+
+impl<T> Default for Map<T> {
+    fn default() -> Self {
+        Self {
+            rooms: HashMap::new(),
+            room_id_provider: RoomId::default(),
+            edge_data: BTreeMap::new(),
+            events: vec![],
+            recording: false,
+        }
+    }
+}
+
+/// Pending changes are not cloned, they belong to the consumers of the original.
+impl<T: Clone> Clone for Map<T> {
+    fn clone(&self) -> Self {
+        Self {
+            rooms: self.rooms.clone(),
+            room_id_provider: self.room_id_provider,
+            edge_data: self.edge_data.clone(),
+            events: vec![],
+            recording: self.recording,
+        }
+    }
+}
+
+fn serialize_sorted<S: serde::Serializer, T: Serialize>(
+    rooms: &HashMap<RoomId, Room<T>>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    serializer.collect_map(rooms.iter().collect::<BTreeMap<_, _>>())
+}
+
+/// Tuple keys can't be JSON object keys, edge data is stored as a list of entries instead.
+mod edge_data_entries {
+    use std::collections::BTreeMap;
+
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    use crate::{EdgeData, RoomId};
+
+    pub fn serialize<S: Serializer>(
+        edge_data: &BTreeMap<(RoomId, RoomId), EdgeData>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(edge_data.iter())
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<BTreeMap<(RoomId, RoomId), EdgeData>, D::Error> {
+        let entries: Vec<((RoomId, RoomId), EdgeData)> = Deserialize::deserialize(deserializer)?;
+        Ok(entries.into_iter().collect())
+    }
 }
 
 #[derive(Error, Debug)]
 pub enum ErrorAdd {
     #[error("Did not find `from` RoomId {0:?}")]
     InexistantFromRoomId(RoomId),
+    #[error("Did not find `to` RoomId {0:?}")]
+    InexistantToRoomId(RoomId),
     #[error("Did not find enough place around `from` RoomId {0:?}")]
     NoPlaceFound(RoomId),
+    #[error("RoomId {0:?} is not connected to RoomId {1:?}")]
+    NotConnected(RoomId, RoomId),
+    #[error("RoomId {0:?} is already used")]
+    RoomIdTaken(RoomId),
+    #[error("Edge data {0:?} has a negative terrain or door time")]
+    InvalidEdgeData(EdgeData),
 }
 
-impl<T: Sized + std::fmt::Debug> Map<T> {
+impl<T> Map<T> {
     pub fn add(
         &mut self,
         from: RoomId,
@@ -37,7 +184,11 @@ impl<T: Sized + std::fmt::Debug> Map<T> {
         nb_tries: u32,
     ) -> Result<RoomId, ErrorAdd> {
         let positions = self.get_positions();
-        let ref_point = self.rooms[&from].position;
+        let ref_point = self
+            .rooms
+            .get(&from)
+            .ok_or(ErrorAdd::InexistantFromRoomId(from))?
+            .position;
         let poisson = Poisson::new();
 
         let pos = get_position_around(nb_tries, poisson, positions, vec![ref_point], rng);
@@ -49,22 +200,45 @@ impl<T: Sized + std::fmt::Debug> Map<T> {
         }
         Err(ErrorAdd::NoPlaceFound(from))
     }
-    pub fn remove(&mut self, id: RoomId) {
-        self.rooms.remove(&id);
+    /// Removes a room and every connection leading to it.
+    pub fn remove(&mut self, id: RoomId) -> Option<Room<T>> {
+        let removed = self.rooms.remove(&id)?;
+        for to in removed.connections.iter() {
+            self.record(MapEvent::EdgeRemoved(id, *to));
+        }
+        let mut incoming: Vec<RoomId> = vec![];
+        for (from, room) in self.rooms.iter_mut() {
+            let len = room.connections.len();
+            room.connections.retain(|c| *c != id);
+            if room.connections.len() != len {
+                incoming.push(*from);
+            }
+        }
+        incoming.sort();
+        for from in incoming {
+            self.record(MapEvent::EdgeRemoved(from, id));
+        }
+        self.record(MapEvent::RoomRemoved(id));
+        self.edge_data
+            .retain(|(from, to), _| *from != id && *to != id);
+        Some(removed)
     }
 
     fn get_positions(&mut self) -> Vec<(f32, f32)> {
-        self.rooms
-            .values()
-            .into_iter()
-            .map(|r| r.position)
-            .collect()
+        self.rooms.values().map(|r| r.position).collect()
     }
 
+    /// Adds a one-way connection, connecting twice the same rooms has no effect.
     pub fn connect(&mut self, from: RoomId, to: RoomId) -> Result<(), ErrorAdd> {
+        if !self.rooms.contains_key(&to) {
+            return Err(ErrorAdd::InexistantToRoomId(to));
+        }
         match self.rooms.entry(from) {
             std::collections::hash_map::Entry::Occupied(mut room) => {
-                room.get_mut().connections.push(to);
+                if !room.get().connections.contains(&to) {
+                    room.get_mut().connections.push(to);
+                    self.record(MapEvent::EdgeAdded(from, to));
+                }
                 Ok(())
             }
             std::collections::hash_map::Entry::Vacant(_) => {
@@ -73,6 +247,50 @@ impl<T: Sized + std::fmt::Debug> Map<T> {
         }
     }
 
+    /// Removes a one-way connection and its data, returns whether it existed.
+    pub fn disconnect(&mut self, from: RoomId, to: RoomId) -> bool {
+        self.edge_data.remove(&(from, to));
+        match self.rooms.get_mut(&from) {
+            Some(room) => {
+                let len = room.connections.len();
+                room.connections.retain(|c| *c != to);
+                let existed = room.connections.len() != len;
+                if existed {
+                    self.record(MapEvent::EdgeRemoved(from, to));
+                }
+                existed
+            }
+            None => false,
+        }
+    }
+
+    /// Sets the data of the connection from `from` to `to`, dropped when they get disconnected.
+    ///
+    /// Negative terrains or door times are refused, pathfinding estimates rely on costs never
+    /// going down.
+    pub fn set_edge_data(
+        &mut self,
+        from: RoomId,
+        to: RoomId,
+        data: EdgeData,
+    ) -> Result<(), ErrorAdd> {
+        let room = self
+            .rooms
+            .get(&from)
+            .ok_or(ErrorAdd::InexistantFromRoomId(from))?;
+        if !room.connections.contains(&to) {
+            return Err(ErrorAdd::NotConnected(from, to));
+        }
+        // Written so NaN is refused too.
+        if !(data.terrain >= 0f32 && data.door_time >= 0f32) {
+            return Err(ErrorAdd::InvalidEdgeData(data));
+        }
+        if self.edge_data.insert((from, to), data) != Some(data) {
+            self.record(MapEvent::EdgeDataChanged(from, to));
+        }
+        Ok(())
+    }
+
     pub fn create_raw(
         &mut self,
         data: T,
@@ -80,6 +298,10 @@ impl<T: Sized + std::fmt::Debug> Map<T> {
         connections: Vec<RoomId>,
     ) -> RoomId {
         let room_id_to_create = self.room_id_provider;
+        self.record(MapEvent::RoomAdded(room_id_to_create));
+        for to in connections.iter() {
+            self.record(MapEvent::EdgeAdded(room_id_to_create, *to));
+        }
         let new_room = Room {
             connections,
             position,
@@ -90,18 +312,269 @@ impl<T: Sized + std::fmt::Debug> Map<T> {
         room_id_to_create
     }
 
+    /// Puts back a room under a given id, like one returned by [`Map::remove`].
+    ///
+    /// Connections to rooms which don't exist are dropped, connections leading to it have to be
+    /// made again.
+    pub fn restore(&mut self, id: RoomId, mut room: Room<T>) -> Result<(), ErrorAdd> {
+        if self.rooms.contains_key(&id) {
+            return Err(ErrorAdd::RoomIdTaken(id));
+        }
+        room.connections
+            .retain(|c| *c == id || self.rooms.contains_key(c));
+        self.record(MapEvent::RoomAdded(id));
+        for to in room.connections.iter() {
+            self.record(MapEvent::EdgeAdded(id, *to));
+        }
+        self.rooms.insert(id, room);
+        if self.room_id_provider <= id {
+            self.room_id_provider = RoomId(id.0 + 1);
+        }
+        Ok(())
+    }
+
+    /// Moves a room, returns `false` if it doesn't exist.
+    pub fn set_position(&mut self, id: RoomId, position: (f32, f32)) -> bool {
+        match self.rooms.get_mut(&id) {
+            Some(room) => {
+                if room.position != position {
+                    room.position = position;
+                    self.record(MapEvent::RoomMoved(id));
+                }
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Replaces the data of a room, returns the previous one.
+    pub fn set_data(&mut self, id: RoomId, data: T) -> Option<T> {
+        let previous = std::mem::replace(&mut self.rooms.get_mut(&id)?.data, data);
+        self.record(MapEvent::DataChanged(id));
+        Some(previous)
+    }
+
+    /// Starts or stops recording changes, stopping drops the pending ones.
+    ///
+    /// Off by default, so maps nobody drains don't keep growing.
+    pub fn record_events(&mut self, recording: bool) {
+        self.recording = recording;
+        if !recording {
+            self.events.clear();
+        }
+    }
+
+    pub fn is_recording_events(&self) -> bool {
+        self.recording
+    }
+
+    fn record(&mut self, event: MapEvent) {
+        if !self.recording {
+            return;
+        }
+        let repeatable = matches!(
+            event,
+            MapEvent::RoomMoved(_) | MapEvent::DataChanged(_) | MapEvent::EdgeDataChanged(..)
+        );
+        if repeatable && self.events.contains(&event) {
+            return;
+        }
+        self.events.push(event);
+    }
+
+    /// Changes recorded since the last call, oldest first.
+    pub fn drain_events(&mut self) -> std::vec::Drain<'_, MapEvent> {
+        self.events.drain(..)
+    }
+
+    /// Changes recorded since the last drain, oldest first.
+    pub fn events(&self) -> &[MapEvent] {
+        &self.events
+    }
+}
+
+impl<T> Map<T> {
     pub fn len(&self) -> usize {
         self.rooms.len()
     }
     pub fn is_empty(&self) -> bool {
         self.len() == 0
     }
-    pub fn iter_mut(&mut self) -> std::collections::hash_map::IterMut<RoomId, Room<T>> {
+    pub fn iter_mut(&mut self) -> std::collections::hash_map::IterMut<'_, RoomId, Room<T>> {
         self.rooms.iter_mut()
     }
-    pub fn iter(&self) -> std::collections::hash_map::Iter<RoomId, Room<T>> {
+    pub fn iter(&self) -> std::collections::hash_map::Iter<'_, RoomId, Room<T>> {
         self.rooms.iter()
     }
+
+    /// Data of the connection from `from` to `to`, the default one if it was never set.
+    pub fn edge_data(&self, from: RoomId, to: RoomId) -> EdgeData {
+        self.edge_data.get(&(from, to)).copied().unwrap_or_default()
+    }
+
+    /// Connections whose data was set, in a stable order.
+    pub fn iter_edge_data(&self) -> impl Iterator<Item = ((RoomId, RoomId), EdgeData)> + '_ {
+        self.edge_data.iter().map(|(edge, data)| (*edge, *data))
+    }
+
+    /// Unique undirected edges, as `(lowest, highest)` pairs sorted for stable output.
+    pub fn edges(&self) -> Vec<(RoomId, RoomId)> {
+        let mut edges: Vec<(RoomId, RoomId)> = self
+            .rooms
+            .iter()
+            .flat_map(|(id, room)| {
+                room.connections
+                    .iter()
+                    .map(move |c| if id < c { (*id, *c) } else { (*c, *id) })
+            })
+            .collect();
+        edges.sort();
+        edges.dedup();
+        edges
+    }
+
+    /// Hop count from `from` to every room reachable through connections.
+    pub fn distances_from(&self, from: RoomId) -> HashMap<RoomId, usize> {
+        let mut distances = HashMap::new();
+        if !self.rooms.contains_key(&from) {
+            return distances;
+        }
+        let mut queue = VecDeque::new();
+        distances.insert(from, 0);
+        queue.push_back(from);
+        while let Some(current) = queue.pop_front() {
+            let distance = distances[&current];
+            for next in self.rooms[&current].connections.iter() {
+                if !self.rooms.contains_key(next) || distances.contains_key(next) {
+                    continue;
+                }
+                distances.insert(*next, distance + 1);
+                queue.push_back(*next);
+            }
+        }
+        distances
+    }
+
+    /// Moves every room of `other` into this map under fresh ids, translated by `offset`.
+    ///
+    /// With `stitch_edges`, facing rooms of both maps (each being the closest room of the other)
+    /// are connected both ways when closer than the given distance.
+    /// Returns the id each room of `other` was given.
+    pub fn merge(
+        &mut self,
+        other: Map<T>,
+        offset: (f32, f32),
+        stitch_edges: Option<f32>,
+    ) -> HashMap<RoomId, RoomId> {
+        let existing = self.sorted_ids();
+        let mut incoming: Vec<(RoomId, Room<T>)> = other.rooms.into_iter().collect();
+        incoming.sort_by_key(|(id, _)| *id);
+        let mapping: HashMap<RoomId, RoomId> = incoming
+            .iter()
+            .enumerate()
+            .map(|(i, (id, _))| (*id, RoomId(self.room_id_provider.0 + i)))
+            .collect();
+        for ((from, to), data) in other.edge_data {
+            if let (Some(from), Some(to)) = (mapping.get(&from), mapping.get(&to)) {
+                self.edge_data.insert((*from, *to), data);
+            }
+        }
+        let mut connections = vec![];
+        for (id, room) in incoming {
+            connections.extend(room.connections.iter().map(|c| (mapping[&id], *c)));
+            let position = (room.position.0 + offset.0, room.position.1 + offset.1);
+            self.create_raw(room.data, position, vec![]);
+        }
+        // Connected once every room exists, so edge events always refer to added rooms.
+        for (from, to) in connections {
+            if let Some(to) = mapping.get(&to) {
+                let _ = self.connect(from, *to);
+            }
+        }
+        if let Some(max_distance) = stitch_edges {
+            let mut added: Vec<RoomId> = mapping.values().copied().collect();
+            added.sort();
+            for (from, to) in self.facing_rooms(&existing, &added, max_distance) {
+                let _ = self.connect(from, to);
+                let _ = self.connect(to, from);
+            }
+        }
+        mapping
+    }
+
+    /// Pairs of mutually closest rooms between `a` and `b`, closer than `max_distance`.
+    fn facing_rooms(&self, a: &[RoomId], b: &[RoomId], max_distance: f32) -> Vec<(RoomId, RoomId)> {
+        let closest = |id: RoomId, others: &[RoomId]| {
+            let position = self.rooms[&id].position;
+            others
+                .iter()
+                .map(|o| {
+                    (
+                        *o,
+                        poisson::distance_squared(&position, &self.rooms[o].position),
+                    )
+                })
+                .min_by(|(_, d1), (_, d2)| d1.total_cmp(d2))
+        };
+        let mut pairs = vec![];
+        for id_a in a.iter() {
+            if let Some((id_b, distance_squared)) = closest(*id_a, b) {
+                if distance_squared <= max_distance * max_distance
+                    && closest(id_b, a).map(|(c, _)| c) == Some(*id_a)
+                {
+                    pairs.push((*id_a, id_b));
+                }
+            }
+        }
+        pairs
+    }
+
+    /// Copies the given rooms into a standalone map, keeping their ids.
+    ///
+    /// Connections to rooms outside of `ids` are dropped.
+    pub fn subgraph(&self, ids: &[RoomId]) -> Map<T>
+    where
+        T: Clone,
+    {
+        let rooms = ids
+            .iter()
+            .filter_map(|id| self.rooms.get(id).map(|room| (*id, room)))
+            .map(|(id, room)| {
+                let connections = room
+                    .connections
+                    .iter()
+                    .filter(|c| ids.contains(c))
+                    .copied()
+                    .collect();
+                let room = Room {
+                    connections,
+                    position: room.position,
+                    data: room.data.clone(),
+                };
+                (id, room)
+            })
+            .collect();
+        let edge_data = self
+            .edge_data
+            .iter()
+            .filter(|((from, to), _)| ids.contains(from) && ids.contains(to))
+            .map(|(edge, data)| (*edge, *data))
+            .collect();
+        Map {
+            rooms,
+            room_id_provider: self.room_id_provider,
+            edge_data,
+            events: vec![],
+            recording: false,
+        }
+    }
+
+    /// Room ids in ascending order, for algorithms which must not depend on `HashMap` ordering.
+    pub fn sorted_ids(&self) -> Vec<RoomId> {
+        let mut ids: Vec<RoomId> = self.rooms.keys().copied().collect();
+        ids.sort();
+        ids
+    }
 }
 
 pub fn get_position_around(
@@ -113,7 +586,7 @@ pub fn get_position_around(
 ) -> Option<(f32, f32)> {
     for ref_point in ref_points.iter() {
         if let Some(new_position) =
-            poisson.compute_new_position(&positions, ref_point, 40f32, nb_tries, rng)
+            poisson.compute_new_position(&positions, ref_point, ROOM_SPACING, nb_tries, rng)
         {
             return Some(new_position);
         }
@@ -0,0 +1,231 @@
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+
+use rand::{seq::SliceRandom, Rng};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::{Map, RoomId};
+
+/// Keys are tracked in a `u64` bitset during the search.
+pub const MAX_KEYS: usize = 64;
+
+#[derive(
+    PartialOrd, Ord, PartialEq, Eq, Hash, Default, Clone, Copy, Debug, Serialize, Deserialize,
+)]
+pub struct KeyId(usize);
+
+/// Locked edges of a map and the rooms holding their keys.
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct LockAndKey {
+    pub start: RoomId,
+    /// Locked edges as `(lowest, highest)` pairs, locks apply in both directions.
+    pub locks: BTreeMap<(RoomId, RoomId), KeyId>,
+    pub keys: BTreeMap<KeyId, RoomId>,
+}
+
+#[derive(Error, Debug, PartialEq, Eq)]
+pub enum LockError {
+    #[error("Did not find `start` RoomId {0:?}")]
+    InexistantStartRoomId(RoomId),
+    #[error("At most {MAX_KEYS} locks are supported, asked for {0}")]
+    TooManyLocks(usize),
+    #[error("Only {available} edges can be locked, asked for {needed}")]
+    NotEnoughEdges { needed: usize, available: usize },
+    #[error("Did not find a solvable layout")]
+    NoSolvableLayout,
+}
+
+impl LockAndKey {
+    pub fn lock_of(&self, a: RoomId, b: RoomId) -> Option<KeyId> {
+        self.locks.get(&ordered(a, b)).copied()
+    }
+
+    pub fn keys_in(&self, room: RoomId) -> Vec<KeyId> {
+        self.keys
+            .iter()
+            .filter(|(_, r)| **r == room)
+            .map(|(key, _)| *key)
+            .collect()
+    }
+
+    /// Every room reachable without locks can be reached, and every key picked up.
+    pub fn is_solvable<T>(&self, map: &Map<T>) -> bool {
+        self.solve(map).is_some()
+    }
+
+    /// Searches the `(room, keys held)` state space from `start`.
+    ///
+    /// Returns the keys in the order they are picked up, or `None` if some room or key can't
+    /// be reached.
+    pub fn solve<T>(&self, map: &Map<T>) -> Option<Vec<KeyId>> {
+        if !map.rooms.contains_key(&self.start) || self.keys.len() > MAX_KEYS {
+            return None;
+        }
+        let key_bits: HashMap<KeyId, u64> = self
+            .keys
+            .keys()
+            .enumerate()
+            .map(|(i, key)| (*key, 1u64 << i))
+            .collect();
+        let all_keys = key_bits.values().fold(0u64, |all, bit| all | bit);
+        let pick_up = |room: RoomId, held: u64| {
+            self.keys_in(room)
+                .iter()
+                .fold(held, |held, key| held | key_bits[key])
+        };
+
+        let start_state = (self.start, pick_up(self.start, 0));
+        let mut came_from: HashMap<(RoomId, u64), (RoomId, u64)> = HashMap::new();
+        let mut visited = HashSet::from([start_state]);
+        let mut visited_rooms = HashSet::from([self.start]);
+        let mut queue = VecDeque::from([start_state]);
+        let mut best = start_state;
+        while let Some((room, held)) = queue.pop_front() {
+            if held.count_ones() > best.1.count_ones() {
+                best = (room, held);
+            }
+            for next in map.rooms[&room].connections.iter() {
+                if !map.rooms.contains_key(next) {
+                    continue;
+                }
+                if let Some(key) = self.lock_of(room, *next) {
+                    if held & key_bits.get(&key).copied().unwrap_or(0) == 0 {
+                        continue;
+                    }
+                }
+                let state = (*next, pick_up(*next, held));
+                if visited.insert(state) {
+                    visited_rooms.insert(*next);
+                    came_from.insert(state, (room, held));
+                    queue.push_back(state);
+                }
+            }
+        }
+        if best.1 != all_keys || visited_rooms.len() != map.distances_from(self.start).len() {
+            return None;
+        }
+
+        let mut states = vec![best];
+        while let Some(previous) = came_from.get(states.last().unwrap()) {
+            states.push(*previous);
+        }
+        states.reverse();
+        let mut order = vec![];
+        let mut held = 0u64;
+        for (_, state_keys) in states {
+            for (key, bit) in self.keys.keys().map(|key| (*key, key_bits[key])) {
+                if state_keys & bit != 0 && held & bit == 0 {
+                    order.push(key);
+                }
+            }
+            held = state_keys;
+        }
+        Some(order)
+    }
+
+    /// Rooms reachable from `start` picking up every reachable key on the way.
+    fn reachable_rooms<T>(&self, map: &Map<T>) -> HashSet<RoomId> {
+        let mut held: HashSet<KeyId> = HashSet::new();
+        loop {
+            let mut visited = HashSet::from([self.start]);
+            let mut stack = vec![self.start];
+            while let Some(room) = stack.pop() {
+                for next in map.rooms[&room].connections.iter() {
+                    let open = self
+                        .lock_of(room, *next)
+                        .is_none_or(|key| held.contains(&key));
+                    if open && map.rooms.contains_key(next) && visited.insert(*next) {
+                        stack.push(*next);
+                    }
+                }
+            }
+            let picked: HashSet<KeyId> = self
+                .keys
+                .iter()
+                .filter(|(_, room)| visited.contains(room))
+                .map(|(key, _)| *key)
+                .collect();
+            if picked.len() == held.len() {
+                return visited;
+            }
+            held = picked;
+        }
+    }
+}
+
+/// Locks `locks` edges reachable from `start`, each key being placed where it can be reached
+/// before its lock is needed.
+///
+/// Locks on cycles are allowed: the key may sit behind another lock, or be reached the long way
+/// around, the state space search checks every layout before it is returned.
+pub fn generate<T>(
+    map: &Map<T>,
+    start: RoomId,
+    locks: usize,
+    rng: &mut impl Rng,
+    attempts: usize,
+) -> Result<LockAndKey, LockError> {
+    if !map.rooms.contains_key(&start) {
+        return Err(LockError::InexistantStartRoomId(start));
+    }
+    if locks > MAX_KEYS {
+        return Err(LockError::TooManyLocks(locks));
+    }
+    let reachable = map.distances_from(start);
+    let edges: Vec<(RoomId, RoomId)> = map
+        .edges()
+        .into_iter()
+        .filter(|(a, b)| reachable.contains_key(a) && reachable.contains_key(b))
+        .collect();
+    if edges.len() < locks {
+        return Err(LockError::NotEnoughEdges {
+            needed: locks,
+            available: edges.len(),
+        });
+    }
+
+    for _ in 0..attempts {
+        let mut layout = LockAndKey {
+            start,
+            ..Default::default()
+        };
+        let mut candidates = edges.clone();
+        candidates.shuffle(rng);
+        for edge in candidates {
+            if layout.locks.len() == locks {
+                break;
+            }
+            let key = KeyId(layout.keys.len());
+            // Key rooms reachable with every previous key but without crossing the new lock.
+            let mut before_lock = layout.clone();
+            before_lock.locks.insert(edge, key);
+            let mut key_rooms: Vec<RoomId> = before_lock
+                .reachable_rooms(map)
+                .into_iter()
+                .filter(|room| *room != start)
+                .collect();
+            if key_rooms.is_empty() {
+                continue;
+            }
+            key_rooms.sort();
+            before_lock
+                .keys
+                .insert(key, *key_rooms.choose(rng).unwrap());
+            if before_lock.is_solvable(map) {
+                layout = before_lock;
+            }
+        }
+        if layout.locks.len() == locks && layout.is_solvable(map) {
+            return Ok(layout);
+        }
+    }
+    Err(LockError::NoSolvableLayout)
+}
+
+fn ordered(a: RoomId, b: RoomId) -> (RoomId, RoomId) {
+    if a < b {
+        (a, b)
+    } else {
+        (b, a)
+    }
+}
@@ -0,0 +1,184 @@
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+
+use serde::Serialize;
+
+use crate::{Map, RoomId};
+
+/// Summary used to score a generated level.
+#[derive(Debug, Clone, Serialize)]
+pub struct MapMetrics {
+    pub rooms: usize,
+    pub edges: usize,
+    pub components: usize,
+    pub diameter: usize,
+    pub average_shortest_path: f32,
+    /// Highest betweenness, a high value means most paths go through a single chokepoint.
+    pub max_betweenness: f32,
+    pub degree_distribution: BTreeMap<usize, usize>,
+    pub clustering_coefficient: f32,
+    pub dead_ends: usize,
+}
+
+impl MapMetrics {
+    pub fn compute<T>(map: &Map<T>) -> Self {
+        Self {
+            rooms: map.rooms.len(),
+            edges: map.edges().len(),
+            components: component_count(map),
+            diameter: diameter(map),
+            average_shortest_path: average_shortest_path(map),
+            max_betweenness: betweenness_centrality(map)
+                .into_values()
+                .fold(0f32, f32::max),
+            degree_distribution: degree_distribution(map),
+            clustering_coefficient: clustering_coefficient(map),
+            dead_ends: dead_end_count(map),
+        }
+    }
+}
+
+/// Longest shortest path, in hops, between two rooms of the same component.
+pub fn diameter<T>(map: &Map<T>) -> usize {
+    map.rooms
+        .keys()
+        .filter_map(|id| map.distances_from(*id).into_values().max())
+        .max()
+        .unwrap_or(0)
+}
+
+/// Number of connected components, ignoring the direction of connections.
+pub fn component_count<T>(map: &Map<T>) -> usize {
+    let adjacency = undirected_adjacency(map);
+    let mut visited = HashSet::new();
+    let mut count = 0;
+    for id in map.sorted_ids() {
+        if !visited.insert(id) {
+            continue;
+        }
+        count += 1;
+        let mut stack = vec![id];
+        while let Some(current) = stack.pop() {
+            for next in adjacency[&current].iter() {
+                if visited.insert(*next) {
+                    stack.push(*next);
+                }
+            }
+        }
+    }
+    count
+}
+
+/// Neighbours of every room, with one-way connections made two-way.
+pub fn undirected_adjacency<T>(map: &Map<T>) -> HashMap<RoomId, Vec<RoomId>> {
+    let mut adjacency: HashMap<RoomId, Vec<RoomId>> =
+        map.rooms.keys().map(|id| (*id, vec![])).collect();
+    for (from, to) in map.edges() {
+        if !adjacency.contains_key(&from) || !adjacency.contains_key(&to) {
+            continue;
+        }
+        adjacency.get_mut(&from).unwrap().push(to);
+        adjacency.get_mut(&to).unwrap().push(from);
+    }
+    adjacency
+}
+
+/// Mean hop count over every ordered pair of distinct rooms connected by a path.
+pub fn average_shortest_path<T>(map: &Map<T>) -> f32 {
+    let (mut total, mut pairs) = (0usize, 0usize);
+    for id in map.rooms.keys() {
+        for distance in map.distances_from(*id).into_values() {
+            if distance > 0 {
+                total += distance;
+                pairs += 1;
+            }
+        }
+    }
+    if pairs == 0 {
+        return 0f32;
+    }
+    total as f32 / pairs as f32
+}
+
+/// Brandes' betweenness centrality over connections, not normalized.
+pub fn betweenness_centrality<T>(map: &Map<T>) -> HashMap<RoomId, f32> {
+    let mut centrality: HashMap<RoomId, f32> = map.rooms.keys().map(|id| (*id, 0f32)).collect();
+    for source in map.sorted_ids() {
+        let mut stack = vec![];
+        let mut predecessors: HashMap<RoomId, Vec<RoomId>> = HashMap::new();
+        let mut path_count: HashMap<RoomId, f32> = HashMap::new();
+        let mut distance: HashMap<RoomId, usize> = HashMap::new();
+        path_count.insert(source, 1f32);
+        distance.insert(source, 0);
+        let mut queue = VecDeque::from([source]);
+        while let Some(current) = queue.pop_front() {
+            stack.push(current);
+            for next in map.rooms[&current].connections.iter() {
+                if !map.rooms.contains_key(next) {
+                    continue;
+                }
+                if !distance.contains_key(next) {
+                    distance.insert(*next, distance[&current] + 1);
+                    queue.push_back(*next);
+                }
+                if distance[next] == distance[&current] + 1 {
+                    *path_count.entry(*next).or_default() += path_count[&current];
+                    predecessors.entry(*next).or_default().push(current);
+                }
+            }
+        }
+        let mut dependency: HashMap<RoomId, f32> = HashMap::new();
+        while let Some(current) = stack.pop() {
+            let current_dependency = dependency.get(&current).copied().unwrap_or_default();
+            for predecessor in predecessors.get(&current).into_iter().flatten() {
+                *dependency.entry(*predecessor).or_default() +=
+                    path_count[predecessor] / path_count[&current] * (1f32 + current_dependency);
+            }
+            if current != source {
+                *centrality.get_mut(&current).unwrap() += current_dependency;
+            }
+        }
+    }
+    centrality
+}
+
+/// Number of rooms for each amount of neighbours.
+pub fn degree_distribution<T>(map: &Map<T>) -> BTreeMap<usize, usize> {
+    let mut distribution = BTreeMap::new();
+    for neighbours in undirected_adjacency(map).values() {
+        *distribution.entry(neighbours.len()).or_default() += 1;
+    }
+    distribution
+}
+
+/// Average local clustering coefficient: how often two neighbours of a room are connected.
+pub fn clustering_coefficient<T>(map: &Map<T>) -> f32 {
+    if map.rooms.is_empty() {
+        return 0f32;
+    }
+    let adjacency = undirected_adjacency(map);
+    let mut total = 0f32;
+    for neighbours in adjacency.values() {
+        if neighbours.len() < 2 {
+            continue;
+        }
+        let mut links = 0;
+        for (i, a) in neighbours.iter().enumerate() {
+            for b in neighbours.iter().skip(i + 1) {
+                if adjacency[a].contains(b) {
+                    links += 1;
+                }
+            }
+        }
+        let possible = neighbours.len() * (neighbours.len() - 1) / 2;
+        total += links as f32 / possible as f32;
+    }
+    total / map.rooms.len() as f32
+}
+
+/// Rooms with a single neighbour.
+pub fn dead_end_count<T>(map: &Map<T>) -> usize {
+    undirected_adjacency(map)
+        .values()
+        .filter(|neighbours| neighbours.len() == 1)
+        .count()
+}
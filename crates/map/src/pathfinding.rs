@@ -0,0 +1,131 @@
+use std::{
+    cmp::Ordering,
+    collections::{BinaryHeap, HashMap},
+};
+
+use crate::{cost, Map, RoomId};
+
+/// A* over connections, edges cost their [`cost::travel_time`] at speed 1.
+pub fn shortest_path<T>(map: &Map<T>, from: RoomId, to: RoomId) -> Option<Vec<RoomId>> {
+    shortest_path_within(map, from, to, |_| true)
+}
+
+/// Same as [`shortest_path`], only going through rooms accepted by `allowed`.
+pub fn shortest_path_within<T>(
+    map: &Map<T>,
+    from: RoomId,
+    to: RoomId,
+    allowed: impl Fn(RoomId) -> bool,
+) -> Option<Vec<RoomId>> {
+    fastest_path_within(map, from, to, 1f32, allowed).map(|(path, _)| path)
+}
+
+/// Quickest path for a unit moving at `speed`, and its travel time in seconds.
+///
+/// Door times don't depend on speed, so faster units favour corridors without doors.
+pub fn fastest_path<T>(
+    map: &Map<T>,
+    from: RoomId,
+    to: RoomId,
+    speed: f32,
+) -> Option<(Vec<RoomId>, f32)> {
+    fastest_path_within(map, from, to, speed, |_| true)
+}
+
+/// Same as [`fastest_path`], only going through rooms accepted by `allowed`.
+pub fn fastest_path_within<T>(
+    map: &Map<T>,
+    from: RoomId,
+    to: RoomId,
+    speed: f32,
+    allowed: impl Fn(RoomId) -> bool,
+) -> Option<(Vec<RoomId>, f32)> {
+    let length = |from, to| cost::edge_length(map, from, to).unwrap();
+    let allowed = |_, next| next == to || allowed(next);
+    fastest_path_along(map, from, to, speed, allowed, length)
+}
+
+/// Same as [`fastest_path_within`], only following the connections accepted by `allowed`, each
+/// being `length(from, to)` long instead of going straight, see [`cost::travel_time_over`].
+///
+/// Lengths shorter than the straight distance between the rooms may miss the fastest path.
+pub fn fastest_path_along<T>(
+    map: &Map<T>,
+    from: RoomId,
+    to: RoomId,
+    speed: f32,
+    allowed: impl Fn(RoomId, RoomId) -> bool,
+    length: impl Fn(RoomId, RoomId) -> f32,
+) -> Option<(Vec<RoomId>, f32)> {
+    if !map.rooms.contains_key(&from) || !map.rooms.contains_key(&to) {
+        return None;
+    }
+    let target = map.rooms[&to].position;
+    let min_terrain = cost::min_terrain(map);
+    let heuristic = |id: RoomId| {
+        poisson::distance_squared(&map.rooms[&id].position, &target).sqrt() * min_terrain / speed
+    };
+
+    let mut costs: HashMap<RoomId, f32> = HashMap::from([(from, 0f32)]);
+    let mut came_from: HashMap<RoomId, RoomId> = HashMap::new();
+    let mut open = BinaryHeap::from([Candidate {
+        estimate: heuristic(from),
+        room: from,
+    }]);
+    while let Some(Candidate { room, estimate }) = open.pop() {
+        if room == to {
+            let mut path = vec![to];
+            while let Some(previous) = came_from.get(path.last().unwrap()) {
+                path.push(*previous);
+            }
+            path.reverse();
+            return Some((path, costs[&to]));
+        }
+        let cost = costs[&room];
+        if estimate > cost + heuristic(room) {
+            // Outdated entry, a cheaper one was already processed.
+            continue;
+        }
+        for next in map.rooms[&room].connections.iter() {
+            if !map.rooms.contains_key(next) || !allowed(room, *next) {
+                continue;
+            }
+            let next_cost =
+                cost + cost::travel_time_over(map, room, *next, length(room, *next), speed);
+            if costs.get(next).is_some_and(|c| *c <= next_cost) {
+                continue;
+            }
+            costs.insert(*next, next_cost);
+            came_from.insert(*next, room);
+            open.push(Candidate {
+                estimate: next_cost + heuristic(*next),
+                room: *next,
+            });
+        }
+    }
+    None
+}
+
+#[derive(PartialEq)]
+struct Candidate {
+    estimate: f32,
+    room: RoomId,
+}
+
+impl Eq for Candidate {}
+
+impl Ord for Candidate {
+    fn cmp(&self, other: &Self) -> Ordering {
+        // Reversed: `BinaryHeap` pops the greatest, we want the lowest estimate first.
+        other
+            .estimate
+            .total_cmp(&self.estimate)
+            .then_with(|| other.room.cmp(&self.room))
+    }
+}
+
+impl PartialOrd for Candidate {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
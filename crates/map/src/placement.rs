@@ -0,0 +1,273 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    hash::Hash,
+};
+
+use rand::{seq::SliceRandom, Rng};
+use thiserror::Error;
+
+use crate::{Map, RoomId};
+
+/// Rule between two roles, `R` being the game's own role type.
+#[derive(Debug, Clone)]
+pub enum Constraint<R> {
+    /// Every room of `role` is at least `hops` away from every room of `from`, and reachable
+    /// from it.
+    MinHops { role: R, from: R, hops: usize },
+    /// Every room of `role` is at most `hops` away from every room of `from`.
+    MaxHops { role: R, from: R, hops: usize },
+    /// Rooms of `role` are reached through different neighbours of the room of `root`.
+    DistinctBranches { role: R, root: R },
+}
+
+#[derive(Debug, Clone)]
+pub struct PlacementRequest<R> {
+    /// Roles to assign, in assignment order, with how many rooms each needs.
+    pub roles: Vec<(R, usize)>,
+    pub constraints: Vec<Constraint<R>>,
+    /// Maximum number of tentative assignments before giving up.
+    pub max_steps: usize,
+}
+
+impl<R> PlacementRequest<R> {
+    pub fn new(roles: Vec<(R, usize)>, constraints: Vec<Constraint<R>>) -> Self {
+        Self {
+            roles,
+            constraints,
+            max_steps: 100_000,
+        }
+    }
+}
+
+#[derive(Error, Debug, PartialEq, Eq)]
+pub enum PlacementError {
+    #[error("Roles need {needed} rooms but the map has {available}")]
+    NotEnoughRooms { needed: usize, available: usize },
+    #[error("No placement satisfies the constraints")]
+    Unsatisfiable,
+    #[error("Gave up after {0} steps without finding a placement")]
+    SearchExhausted(usize),
+}
+
+/// Rooms tagged with the role they were given, a room has at most one role.
+#[derive(Debug, Clone)]
+pub struct Placement<R: Eq + Hash> {
+    pub rooms: HashMap<R, Vec<RoomId>>,
+}
+
+impl<R: Eq + Hash> Placement<R> {
+    pub fn rooms_of(&self, role: &R) -> &[RoomId] {
+        self.rooms.get(role).map(Vec::as_slice).unwrap_or_default()
+    }
+
+    pub fn role_of(&self, room: RoomId) -> Option<&R> {
+        self.rooms
+            .iter()
+            .find(|(_, rooms)| rooms.contains(&room))
+            .map(|(role, _)| role)
+    }
+}
+
+/// Assigns rooms to roles with a randomized backtracking search.
+pub fn place<T, R: Eq + Hash + Clone>(
+    map: &Map<T>,
+    request: &PlacementRequest<R>,
+    rng: &mut impl Rng,
+) -> Result<Placement<R>, PlacementError> {
+    let slots: Vec<R> = request
+        .roles
+        .iter()
+        .flat_map(|(role, count)| std::iter::repeat_n(role.clone(), *count))
+        .collect();
+    if slots.len() > map.len() {
+        return Err(PlacementError::NotEnoughRooms {
+            needed: slots.len(),
+            available: map.len(),
+        });
+    }
+    let mut solver = Solver {
+        map,
+        constraints: &request.constraints,
+        slots: &slots,
+        distances: HashMap::new(),
+        branches: HashMap::new(),
+        steps: 0,
+        max_steps: request.max_steps,
+    };
+    let mut assigned = vec![];
+    if !solver.search(&mut assigned, rng)? {
+        return Err(PlacementError::Unsatisfiable);
+    }
+    let mut rooms: HashMap<R, Vec<RoomId>> = HashMap::new();
+    for (role, room) in slots.into_iter().zip(assigned) {
+        rooms.entry(role).or_default().push(room);
+    }
+    Ok(Placement { rooms })
+}
+
+struct Solver<'a, T, R> {
+    map: &'a Map<T>,
+    constraints: &'a [Constraint<R>],
+    slots: &'a [R],
+    distances: HashMap<RoomId, HashMap<RoomId, usize>>,
+    branches: HashMap<RoomId, HashMap<RoomId, RoomId>>,
+    steps: usize,
+    max_steps: usize,
+}
+
+impl<'a, T, R: Eq + Clone> Solver<'a, T, R> {
+    fn search(
+        &mut self,
+        assigned: &mut Vec<RoomId>,
+        rng: &mut impl Rng,
+    ) -> Result<bool, PlacementError> {
+        if assigned.len() == self.slots.len() {
+            return Ok(true);
+        }
+        let mut candidates = self.map.sorted_ids();
+        candidates.shuffle(rng);
+        for candidate in candidates {
+            if assigned.contains(&candidate) {
+                continue;
+            }
+            self.steps += 1;
+            if self.steps > self.max_steps {
+                return Err(PlacementError::SearchExhausted(self.max_steps));
+            }
+            assigned.push(candidate);
+            if self.is_consistent(assigned) && self.search(assigned, rng)? {
+                return Ok(true);
+            }
+            assigned.pop();
+        }
+        Ok(false)
+    }
+
+    /// Checks the last assigned room against the ones assigned before it.
+    fn is_consistent(&mut self, assigned: &[RoomId]) -> bool {
+        let (slots, constraints) = (self.slots, self.constraints);
+        let role = &slots[assigned.len() - 1];
+        for constraint in constraints.iter() {
+            let satisfied = match constraint {
+                Constraint::MinHops {
+                    role: constrained,
+                    from,
+                    hops,
+                } => self.pairs(assigned, role, constrained, from).iter().all(
+                    |(from_room, role_room)| {
+                        self.distance(*from_room, *role_room)
+                            .is_some_and(|distance| distance >= *hops)
+                    },
+                ),
+                Constraint::MaxHops {
+                    role: constrained,
+                    from,
+                    hops,
+                } => self.pairs(assigned, role, constrained, from).iter().all(
+                    |(from_room, role_room)| {
+                        self.distance(*from_room, *role_room)
+                            .is_some_and(|distance| distance <= *hops)
+                    },
+                ),
+                Constraint::DistinctBranches {
+                    role: constrained,
+                    root,
+                } => self.distinct_branches(assigned, constrained, root),
+            };
+            if !satisfied {
+                return false;
+            }
+        }
+        true
+    }
+
+    /// `(from room, role room)` pairs involving the last assigned room.
+    fn pairs(
+        &self,
+        assigned: &[RoomId],
+        last_role: &R,
+        role: &R,
+        from: &R,
+    ) -> Vec<(RoomId, RoomId)> {
+        let last = assigned[assigned.len() - 1];
+        let mut pairs = vec![];
+        for (other, other_role) in assigned.iter().zip(self.slots.iter()) {
+            if last_role == role && other_role == from && *other != last {
+                pairs.push((*other, last));
+            }
+            if last_role == from && other_role == role && *other != last {
+                pairs.push((last, *other));
+            }
+        }
+        pairs
+    }
+
+    fn distance(&mut self, from: RoomId, to: RoomId) -> Option<usize> {
+        let map = self.map;
+        self.distances
+            .entry(from)
+            .or_insert_with(|| map.distances_from(from))
+            .get(&to)
+            .copied()
+    }
+
+    fn distinct_branches(&mut self, assigned: &[RoomId], role: &R, root: &R) -> bool {
+        let root_room = match assigned
+            .iter()
+            .zip(self.slots.iter())
+            .find(|(_, r)| *r == root)
+        {
+            Some((room, _)) => *room,
+            None => return true,
+        };
+        let map = self.map;
+        let branches = self
+            .branches
+            .entry(root_room)
+            .or_insert_with(|| branches_from(map, root_room));
+        let mut used = vec![];
+        for (room, _) in assigned
+            .iter()
+            .zip(self.slots.iter())
+            .filter(|(_, r)| *r == role)
+        {
+            match branches.get(room) {
+                Some(branch) if !used.contains(branch) => used.push(*branch),
+                _ => return false,
+            }
+        }
+        true
+    }
+}
+
+/// For every room reachable from `root`, the neighbour of `root` its shortest path goes through.
+pub fn branches_from<T>(map: &Map<T>, root: RoomId) -> HashMap<RoomId, RoomId> {
+    let mut branches = HashMap::new();
+    if !map.rooms.contains_key(&root) {
+        return branches;
+    }
+    let mut queue = VecDeque::new();
+    let mut first_hops = map.rooms[&root].connections.clone();
+    first_hops.sort();
+    for neighbour in first_hops {
+        if neighbour != root && map.rooms.contains_key(&neighbour) {
+            if let std::collections::hash_map::Entry::Vacant(e) = branches.entry(neighbour) {
+                e.insert(neighbour);
+                queue.push_back(neighbour);
+            }
+        }
+    }
+    while let Some(current) = queue.pop_front() {
+        let branch = branches[&current];
+        let mut next_rooms = map.rooms[&current].connections.clone();
+        next_rooms.sort();
+        for next in next_rooms {
+            if next == root || branches.contains_key(&next) || !map.rooms.contains_key(&next) {
+                continue;
+            }
+            branches.insert(next, branch);
+            queue.push_back(next);
+        }
+    }
+    branches
+}
@@ -0,0 +1,302 @@
+//! Conversion of a map into a tile grid, for tools expecting grid dungeons.
+
+use serde::{Deserialize, Serialize};
+
+use crate::Map;
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Tile {
+    #[default]
+    Empty,
+    Floor,
+    Corridor,
+    Wall,
+}
+
+impl Tile {
+    pub fn is_walkable(&self) -> bool {
+        matches!(self, Tile::Floor | Tile::Corridor)
+    }
+
+    pub fn to_char(&self) -> char {
+        match self {
+            Tile::Empty => ' ',
+            Tile::Floor => '.',
+            Tile::Corridor => ',',
+            Tile::Wall => '#',
+        }
+    }
+}
+
+/// Footprint of a room around its position, in world units.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum RoomShape {
+    Rectangle { width: f32, height: f32 },
+    Circle { radius: f32 },
+}
+
+impl RoomShape {
+    fn half_extents(&self) -> (f32, f32) {
+        match self {
+            RoomShape::Rectangle { width, height } => (width / 2f32, height / 2f32),
+            RoomShape::Circle { radius } => (*radius, *radius),
+        }
+    }
+
+    fn contains(&self, offset: (f32, f32)) -> bool {
+        match self {
+            RoomShape::Rectangle { width, height } => {
+                offset.0.abs() <= width / 2f32 && offset.1.abs() <= height / 2f32
+            }
+            RoomShape::Circle { radius } => {
+                offset.0 * offset.0 + offset.1 * offset.1 <= radius * radius
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CorridorStyle {
+    /// Horizontal then vertical.
+    LShaped,
+    Bresenham,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RasterConfig {
+    /// World units covered by a cell.
+    pub cell_size: f32,
+    pub corridor_style: CorridorStyle,
+    /// Surrounds floors and corridors with walls.
+    pub walls: bool,
+    /// Empty cells kept around the rooms.
+    pub margin: usize,
+}
+
+impl Default for RasterConfig {
+    fn default() -> Self {
+        Self {
+            cell_size: 10f32,
+            corridor_style: CorridorStyle::LShaped,
+            walls: true,
+            margin: 1,
+        }
+    }
+}
+
+/// Cells stored row by row, `(0, 0)` being the cell at the lowest x and y.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Grid<T> {
+    pub width: usize,
+    pub height: usize,
+    /// World position of the lowest corner of cell `(0, 0)`.
+    pub origin: (f32, f32),
+    pub cell_size: f32,
+    cells: Vec<T>,
+}
+
+impl<T: Clone + Default> Grid<T> {
+    pub fn new(width: usize, height: usize, origin: (f32, f32), cell_size: f32) -> Self {
+        Self {
+            width,
+            height,
+            origin,
+            cell_size,
+            cells: vec![T::default(); width * height],
+        }
+    }
+}
+
+impl<T> Grid<T> {
+    pub fn get(&self, x: usize, y: usize) -> Option<&T> {
+        (x < self.width && y < self.height).then(|| &self.cells[y * self.width + x])
+    }
+
+    pub fn set(&mut self, x: usize, y: usize, value: T) {
+        if x < self.width && y < self.height {
+            self.cells[y * self.width + x] = value;
+        }
+    }
+
+    /// Cell containing a world position, if it is in the grid.
+    pub fn cell_of(&self, position: (f32, f32)) -> Option<(usize, usize)> {
+        let x = ((position.0 - self.origin.0) / self.cell_size).floor();
+        let y = ((position.1 - self.origin.1) / self.cell_size).floor();
+        (x >= 0f32 && y >= 0f32 && (x as usize) < self.width && (y as usize) < self.height)
+            .then_some((x as usize, y as usize))
+    }
+
+    /// World position of the center of a cell.
+    pub fn cell_center(&self, x: usize, y: usize) -> (f32, f32) {
+        (
+            self.origin.0 + (x as f32 + 0.5f32) * self.cell_size,
+            self.origin.1 + (y as f32 + 0.5f32) * self.cell_size,
+        )
+    }
+
+    /// Cells and their coordinates, row by row.
+    pub fn iter(&self) -> impl Iterator<Item = ((usize, usize), &T)> {
+        let width = self.width;
+        self.cells
+            .iter()
+            .enumerate()
+            .map(move |(i, cell)| ((i % width, i / width), cell))
+    }
+}
+
+impl Grid<Tile> {
+    /// One line per row, the highest y first so the text reads like the map is drawn.
+    pub fn to_ascii(&self) -> String {
+        let mut ascii = String::with_capacity((self.width + 1) * self.height);
+        for y in (0..self.height).rev() {
+            for x in 0..self.width {
+                ascii.push(self.cells[y * self.width + x].to_char());
+            }
+            ascii.push('\n');
+        }
+        ascii
+    }
+}
+
+/// Draws rooms with the shape given by `room_shape`, then connections as corridors.
+pub fn rasterize<T>(
+    map: &Map<T>,
+    config: &RasterConfig,
+    room_shape: impl Fn(&T) -> RoomShape,
+) -> Grid<Tile> {
+    let ids = map.sorted_ids();
+    let shapes: Vec<RoomShape> = ids
+        .iter()
+        .map(|id| room_shape(&map.rooms[id].data))
+        .collect();
+    let (mut min, mut max) = (
+        (f32::INFINITY, f32::INFINITY),
+        (f32::NEG_INFINITY, f32::NEG_INFINITY),
+    );
+    for (id, shape) in ids.iter().zip(shapes.iter()) {
+        let (x, y) = map.rooms[id].position;
+        let (half_width, half_height) = shape.half_extents();
+        min = (min.0.min(x - half_width), min.1.min(y - half_height));
+        max = (max.0.max(x + half_width), max.1.max(y + half_height));
+    }
+    let cell_size = config.cell_size;
+    if ids.is_empty() {
+        return Grid::new(0, 0, (0f32, 0f32), cell_size);
+    }
+    let margin = config.margin as f32 * cell_size;
+    let origin = (min.0 - margin, min.1 - margin);
+    let width = ((max.0 - min.0) / cell_size).ceil() as usize + 2 * config.margin + 1;
+    let height = ((max.1 - min.1) / cell_size).ceil() as usize + 2 * config.margin + 1;
+    let mut grid = Grid::new(width, height, origin, cell_size);
+
+    for (id, shape) in ids.iter().zip(shapes.iter()) {
+        let position = map.rooms[id].position;
+        let (half_width, half_height) = shape.half_extents();
+        let low = grid.cell_of((position.0 - half_width, position.1 - half_height));
+        let high = grid.cell_of((position.0 + half_width, position.1 + half_height));
+        let (low, high) = match (low, high) {
+            (Some(low), Some(high)) => (low, high),
+            _ => continue,
+        };
+        for y in low.1..=high.1 {
+            for x in low.0..=high.0 {
+                let center = grid.cell_center(x, y);
+                if shape.contains((center.0 - position.0, center.1 - position.1)) {
+                    grid.set(x, y, Tile::Floor);
+                }
+            }
+        }
+        // Rooms smaller than a cell still get one.
+        if let Some((x, y)) = grid.cell_of(position) {
+            grid.set(x, y, Tile::Floor);
+        }
+    }
+
+    for (a, b) in map.edges() {
+        let from = grid.cell_of(map.rooms[&a].position);
+        let to = grid.cell_of(map.rooms[&b].position);
+        let (from, to) = match (from, to) {
+            (Some(from), Some(to)) => (from, to),
+            _ => continue,
+        };
+        let cells = match config.corridor_style {
+            CorridorStyle::LShaped => l_shaped(from, to),
+            CorridorStyle::Bresenham => bresenham(from, to),
+        };
+        for (x, y) in cells {
+            if grid.get(x, y) != Some(&Tile::Floor) {
+                grid.set(x, y, Tile::Corridor);
+            }
+        }
+    }
+
+    if config.walls {
+        add_walls(&mut grid);
+    }
+    grid
+}
+
+fn l_shaped(from: (usize, usize), to: (usize, usize)) -> Vec<(usize, usize)> {
+    let mut cells: Vec<(usize, usize)> = range(from.0, to.0).map(|x| (x, from.1)).collect();
+    cells.extend(range(from.1, to.1).map(|y| (to.0, y)));
+    cells
+}
+
+/// Cells from `from` to `to` included, in either direction.
+fn range(from: usize, to: usize) -> Box<dyn Iterator<Item = usize>> {
+    if from <= to {
+        Box::new(from..=to)
+    } else {
+        Box::new((to..=from).rev())
+    }
+}
+
+fn bresenham(from: (usize, usize), to: (usize, usize)) -> Vec<(usize, usize)> {
+    let (mut x, mut y) = (from.0 as i64, from.1 as i64);
+    let (to_x, to_y) = (to.0 as i64, to.1 as i64);
+    let (dx, dy) = ((to_x - x).abs(), -(to_y - y).abs());
+    let (step_x, step_y) = ((to_x - x).signum(), (to_y - y).signum());
+    let mut error = dx + dy;
+    let mut cells = vec![];
+    loop {
+        cells.push((x as usize, y as usize));
+        if x == to_x && y == to_y {
+            return cells;
+        }
+        let doubled = 2 * error;
+        if doubled >= dy {
+            error += dy;
+            x += step_x;
+        }
+        if doubled <= dx {
+            error += dx;
+            y += step_y;
+        }
+    }
+}
+
+/// Turns empty cells touching a walkable one, diagonals included, into walls.
+fn add_walls(grid: &mut Grid<Tile>) {
+    let mut walls = vec![];
+    for ((x, y), tile) in grid.iter() {
+        if *tile != Tile::Empty {
+            continue;
+        }
+        let touches_walkable = (-1i64..=1).any(|dy| {
+            (-1i64..=1).any(|dx| {
+                let (nx, ny) = (x as i64 + dx, y as i64 + dy);
+                nx >= 0
+                    && ny >= 0
+                    && grid
+                        .get(nx as usize, ny as usize)
+                        .is_some_and(Tile::is_walkable)
+            })
+        });
+        if touches_walkable {
+            walls.push((x, y));
+        }
+    }
+    for (x, y) in walls {
+        grid.set(x, y, Tile::Wall);
+    }
+}
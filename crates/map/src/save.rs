@@ -0,0 +1,129 @@
+//! Versioned save files, older versions are migrated when loaded.
+//!
+//! Versions:
+//! 1. The bare [`Map`] json.
+//! 2. [`SaveFile`]: the map with the generator, config and seed which made it.
+//! 3. Adds the [`LayoutConfig`] which relaxed the generated map.
+
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::{json, Value};
+use thiserror::Error;
+
+use crate::{
+    generator::{Generator, GeneratorConfig},
+    layout::LayoutConfig,
+    Map,
+};
+
+pub const FORMAT_VERSION: u64 = 3;
+
+/// Migrations from each version to the next one, the first one migrating version 1.
+const MIGRATIONS: &[fn(Value) -> Value] = &[wrap_bare_map, add_layout];
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SaveFile<T> {
+    pub format_version: u64,
+    /// Name of the generator which made the map, `None` for maps made by hand.
+    pub generator: Option<String>,
+    pub config: Option<GeneratorConfig>,
+    pub seed: Option<u64>,
+    /// Relaxation run with the same random generator after generating, `None` if there was none.
+    pub layout: Option<LayoutConfig>,
+    pub map: Map<T>,
+}
+
+#[derive(Error, Debug)]
+pub enum SaveError {
+    #[error("Could not read or write save: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("Save format version {0} is newer than the supported {FORMAT_VERSION}")]
+    UnsupportedVersion(u64),
+    #[error("Save is neither a map nor a versioned save file")]
+    UnknownFormat,
+}
+
+impl<T> SaveFile<T> {
+    pub fn new(map: Map<T>) -> Self {
+        Self {
+            format_version: FORMAT_VERSION,
+            generator: None,
+            config: None,
+            seed: None,
+            layout: None,
+            map,
+        }
+    }
+
+    /// Save of a map made by [`Generator`].
+    pub fn generated(map: Map<T>, config: GeneratorConfig, seed: u64) -> Self {
+        Self {
+            generator: Some(Generator::NAME.to_string()),
+            config: Some(config),
+            seed: Some(seed),
+            ..Self::new(map)
+        }
+    }
+
+    /// Records the layout which relaxed the generated map.
+    pub fn with_layout(mut self, layout: LayoutConfig) -> Self {
+        self.layout = Some(layout);
+        self
+    }
+}
+
+impl<T: Serialize> SaveFile<T> {
+    pub fn to_json(&self) -> Result<String, SaveError> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+}
+
+impl<T: DeserializeOwned> SaveFile<T> {
+    /// Loads a save of any version up to [`FORMAT_VERSION`].
+    pub fn from_json(source: &str) -> Result<Self, SaveError> {
+        let value: Value = serde_json::from_str(source)?;
+        Ok(serde_json::from_value(migrate(value)?)?)
+    }
+}
+
+/// Migrates a save of any version to the current one.
+pub fn migrate(mut value: Value) -> Result<Value, SaveError> {
+    let mut version = version_of(&value)?;
+    if version > FORMAT_VERSION {
+        return Err(SaveError::UnsupportedVersion(version));
+    }
+    while version < FORMAT_VERSION {
+        value = MIGRATIONS[version as usize - 1](value);
+        version += 1;
+    }
+    Ok(value)
+}
+
+fn version_of(value: &Value) -> Result<u64, SaveError> {
+    if let Some(version) = value.get("format_version") {
+        // Versions start at 1.
+        return version
+            .as_u64()
+            .filter(|version| *version > 0)
+            .ok_or(SaveError::UnknownFormat);
+    }
+    if value.get("rooms").is_some_and(Value::is_object) {
+        return Ok(1);
+    }
+    Err(SaveError::UnknownFormat)
+}
+
+fn wrap_bare_map(map: Value) -> Value {
+    json!({
+        "format_version": 2,
+        "generator": null,
+        "config": null,
+        "seed": null,
+        "map": map,
+    })
+}
+
+fn add_layout(mut save: Value) -> Value {
+    save["format_version"] = json!(3);
+    save["layout"] = Value::Null;
+    save
+}
@@ -0,0 +1,343 @@
+//! Reversible map edits, for editors.
+
+use thiserror::Error;
+
+use crate::{EdgeData, ErrorAdd, Map, Room, RoomId};
+
+/// An edit of a map, applied through [`UndoStack::apply`] so it can be undone.
+#[derive(Debug, Clone)]
+pub enum Edit<T> {
+    AddRoom {
+        data: T,
+        position: (f32, f32),
+    },
+    /// Removes a room and its connections, undoing it restores them.
+    RemoveRoom(RoomId),
+    Connect(RoomId, RoomId),
+    Disconnect(RoomId, RoomId),
+    MoveRoom(RoomId, (f32, f32)),
+    SetData(RoomId, T),
+}
+
+#[derive(Error, Debug)]
+pub enum EditError {
+    #[error("Did not find RoomId {0:?}")]
+    InexistantRoomId(RoomId),
+    #[error(transparent)]
+    Map(#[from] ErrorAdd),
+}
+
+/// Applied edits, grouped in transactions undone and redone at once.
+pub struct UndoStack<T> {
+    undo: Vec<Vec<Step<T>>>,
+    redo: Vec<Vec<Step<T>>>,
+    /// Steps of the open transaction, and how many times it was begun.
+    transaction: Option<(Vec<Step<T>>, usize)>,
+}
+
+impl<T> Default for UndoStack<T> {
+    fn default() -> Self {
+        Self {
+            undo: vec![],
+            redo: vec![],
+            transaction: None,
+        }
+    }
+}
+
+impl<T> UndoStack<T> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Applies an edit, returns the room it applies to: the new one for [`Edit::AddRoom`],
+    /// `from` for connections.
+    ///
+    /// Outside of a transaction, the edit is its own transaction. Applying an edit clears the
+    /// redo history.
+    pub fn apply(&mut self, map: &mut Map<T>, edit: Edit<T>) -> Result<RoomId, EditError> {
+        let (room, step) = match edit {
+            Edit::AddRoom { data, position } => {
+                let room = map.create_raw(data, position, vec![]);
+                (room, Step::Remove(room))
+            }
+            Edit::RemoveRoom(room) => (room, Step::Remove(room).apply(map)?),
+            Edit::Connect(from, to) => {
+                if map
+                    .rooms
+                    .get(&from)
+                    .is_some_and(|r| r.connections.contains(&to))
+                {
+                    // Already connected, undoing must not disconnect it.
+                    return Ok(from);
+                }
+                (
+                    from,
+                    Step::Connect(from, to, EdgeData::default()).apply(map)?,
+                )
+            }
+            Edit::Disconnect(from, to) => (from, Step::Disconnect(from, to).apply(map)?),
+            Edit::MoveRoom(room, position) => (room, Step::Move(room, position).apply(map)?),
+            Edit::SetData(room, data) => (room, Step::SetData(room, data).apply(map)?),
+        };
+        self.redo.clear();
+        match self.transaction.as_mut() {
+            Some((steps, _)) => steps.push(step),
+            None => self.undo.push(vec![step]),
+        }
+        Ok(room)
+    }
+
+    /// Groups the next edits until the matching [`UndoStack::commit`], transactions can nest.
+    pub fn begin(&mut self) {
+        match self.transaction.as_mut() {
+            Some((_, depth)) => *depth += 1,
+            None => self.transaction = Some((vec![], 1)),
+        }
+    }
+
+    /// Closes a transaction, the outermost one becomes a single undo step.
+    pub fn commit(&mut self) {
+        if let Some((steps, depth)) = self.transaction.as_mut() {
+            *depth -= 1;
+            if *depth == 0 {
+                let steps = std::mem::take(steps);
+                self.transaction = None;
+                if !steps.is_empty() {
+                    self.undo.push(steps);
+                }
+            }
+        }
+    }
+
+    /// Undoes the edits of the open transaction, including nested ones, and closes it.
+    ///
+    /// On failure, the map is left as it was and the transaction stays open.
+    pub fn rollback(&mut self, map: &mut Map<T>) -> Result<(), EditError> {
+        let (steps, depth) = match self.transaction.take() {
+            Some(transaction) => transaction,
+            None => return Ok(()),
+        };
+        revert(map, steps).map(|_| ()).map_err(|(error, steps)| {
+            self.transaction = Some((steps, depth));
+            error
+        })
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.undo.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.redo.is_empty()
+    }
+
+    /// Undoes the last transaction, returns `false` if there is none.
+    ///
+    /// Fails if the map was changed outside of the stack in a way preventing it, the map and
+    /// the stack are then left as they were.
+    pub fn undo(&mut self, map: &mut Map<T>) -> Result<bool, EditError> {
+        let steps = match self.undo.pop() {
+            Some(steps) => steps,
+            None => return Ok(false),
+        };
+        match revert(map, steps) {
+            Ok(reverted) => self.redo.push(reverted),
+            Err((error, steps)) => {
+                self.undo.push(steps);
+                return Err(error);
+            }
+        }
+        Ok(true)
+    }
+
+    /// Applies again the last undone transaction, returns `false` if there is none.
+    ///
+    /// Fails like [`UndoStack::undo`].
+    pub fn redo(&mut self, map: &mut Map<T>) -> Result<bool, EditError> {
+        let steps = match self.redo.pop() {
+            Some(steps) => steps,
+            None => return Ok(false),
+        };
+        match revert(map, steps) {
+            Ok(reverted) => self.undo.push(reverted),
+            Err((error, steps)) => {
+                self.redo.push(steps);
+                return Err(error);
+            }
+        }
+        Ok(true)
+    }
+
+    pub fn clear(&mut self) {
+        self.undo.clear();
+        self.redo.clear();
+        self.transaction = None;
+    }
+}
+
+/// Applies the steps backwards, returns the steps reverting them, to apply backwards as well.
+///
+/// Each step is checked before being applied. When one fails, the steps already applied are
+/// reverted too and the steps are given back, leaving the map as it was.
+#[allow(clippy::type_complexity)]
+fn revert<T>(
+    map: &mut Map<T>,
+    mut steps: Vec<Step<T>>,
+) -> Result<Vec<Step<T>>, (EditError, Vec<Step<T>>)> {
+    let mut reverted = vec![];
+    while let Some(step) = steps.pop() {
+        let result = match step.check(map) {
+            Ok(()) => step.apply(map),
+            Err(error) => {
+                steps.push(step);
+                Err(error)
+            }
+        };
+        match result {
+            Ok(inverse) => reverted.push(inverse),
+            Err(error) => {
+                while let Some(inverse) = reverted.pop() {
+                    let step = inverse
+                        .apply(map)
+                        .expect("a step just applied can always be reverted");
+                    steps.push(step);
+                }
+                return Err((error, steps));
+            }
+        }
+    }
+    Ok(reverted)
+}
+
+/// A fully specified edit, applying one returns the step undoing it.
+enum Step<T> {
+    Remove(RoomId),
+    Restore {
+        id: RoomId,
+        room: Room<T>,
+        /// Connections leading to the room, and the data of every connection.
+        incoming: Vec<RoomId>,
+        edge_data: Vec<((RoomId, RoomId), EdgeData)>,
+    },
+    Connect(RoomId, RoomId, EdgeData),
+    Disconnect(RoomId, RoomId),
+    Move(RoomId, (f32, f32)),
+    SetData(RoomId, T),
+}
+
+impl<T> Step<T> {
+    /// Fails when applying the step would, without changing the map.
+    fn check(&self, map: &Map<T>) -> Result<(), EditError> {
+        let exists = |id: &RoomId| {
+            if map.rooms.contains_key(id) {
+                Ok(())
+            } else {
+                Err(EditError::InexistantRoomId(*id))
+            }
+        };
+        match self {
+            Step::Remove(id) | Step::Move(id, _) | Step::SetData(id, _) => exists(id),
+            Step::Restore {
+                id,
+                incoming,
+                edge_data,
+                ..
+            } => {
+                if map.rooms.contains_key(id) {
+                    return Err(ErrorAdd::RoomIdTaken(*id).into());
+                }
+                incoming.iter().try_for_each(exists)?;
+                edge_data
+                    .iter()
+                    .flat_map(|((from, to), _)| [from, to])
+                    .filter(|room| *room != id)
+                    .try_for_each(exists)
+            }
+            Step::Connect(from, to, _) => {
+                exists(from)?;
+                exists(to)
+            }
+            Step::Disconnect(from, to) => {
+                if map
+                    .rooms
+                    .get(from)
+                    .is_some_and(|room| room.connections.contains(to))
+                {
+                    Ok(())
+                } else {
+                    Err(ErrorAdd::NotConnected(*from, *to).into())
+                }
+            }
+        }
+    }
+
+    fn apply(self, map: &mut Map<T>) -> Result<Step<T>, EditError> {
+        match self {
+            Step::Remove(id) => {
+                let mut incoming: Vec<RoomId> = map
+                    .rooms
+                    .iter()
+                    .filter(|(from, room)| **from != id && room.connections.contains(&id))
+                    .map(|(from, _)| *from)
+                    .collect();
+                incoming.sort();
+                let edge_data = map
+                    .iter_edge_data()
+                    .filter(|((from, to), _)| *from == id || *to == id)
+                    .collect();
+                let room = map.remove(id).ok_or(EditError::InexistantRoomId(id))?;
+                Ok(Step::Restore {
+                    id,
+                    room,
+                    incoming,
+                    edge_data,
+                })
+            }
+            Step::Restore {
+                id,
+                room,
+                incoming,
+                edge_data,
+            } => {
+                map.restore(id, room)?;
+                for from in incoming {
+                    map.connect(from, id)?;
+                }
+                for ((from, to), data) in edge_data {
+                    map.set_edge_data(from, to, data)?;
+                }
+                Ok(Step::Remove(id))
+            }
+            Step::Connect(from, to, data) => {
+                map.connect(from, to)?;
+                if data != EdgeData::default() {
+                    map.set_edge_data(from, to, data)?;
+                }
+                Ok(Step::Disconnect(from, to))
+            }
+            Step::Disconnect(from, to) => {
+                let data = map.edge_data(from, to);
+                if !map.disconnect(from, to) {
+                    return Err(ErrorAdd::NotConnected(from, to).into());
+                }
+                Ok(Step::Connect(from, to, data))
+            }
+            Step::Move(id, position) => {
+                let previous = map
+                    .rooms
+                    .get(&id)
+                    .ok_or(EditError::InexistantRoomId(id))?
+                    .position;
+                map.set_position(id, position);
+                Ok(Step::Move(id, previous))
+            }
+            Step::SetData(id, data) => {
+                let previous = map
+                    .set_data(id, data)
+                    .ok_or(EditError::InexistantRoomId(id))?;
+                Ok(Step::SetData(id, previous))
+            }
+        }
+    }
+}
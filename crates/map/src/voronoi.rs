@@ -0,0 +1,206 @@
+//! Territories of rooms: the Voronoi diagram of their positions, clipped to a rectangle.
+//!
+//! Each cell is computed by clipping the rectangle with the half-planes closer to its room than
+//! to every other room, which keeps cells convex and makes adding or removing a room local.
+
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{Map, RoomId};
+
+/// Distance under which two cell vertices are considered the same.
+const VERTEX_EPSILON: f32 = 1e-3;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Voronoi {
+    pub min: (f32, f32),
+    pub max: (f32, f32),
+    sites: BTreeMap<RoomId, (f32, f32)>,
+    /// Convex polygons, counter-clockwise.
+    cells: BTreeMap<RoomId, Vec<(f32, f32)>>,
+}
+
+impl Voronoi {
+    /// Diagram of every room, clipped to their bounding box grown by `margin`.
+    pub fn new<T>(map: &Map<T>, margin: f32) -> Self {
+        let (mut min, mut max) = (
+            (f32::INFINITY, f32::INFINITY),
+            (f32::NEG_INFINITY, f32::NEG_INFINITY),
+        );
+        for room in map.rooms.values() {
+            let (x, y) = room.position;
+            min = (min.0.min(x), min.1.min(y));
+            max = (max.0.max(x), max.1.max(y));
+        }
+        if map.is_empty() {
+            (min, max) = ((0f32, 0f32), (0f32, 0f32));
+        }
+        Self::with_bounds(
+            map,
+            (min.0 - margin, min.1 - margin),
+            (max.0 + margin, max.1 + margin),
+        )
+    }
+
+    /// Diagram of every room, clipped to the rectangle from `min` to `max`.
+    pub fn with_bounds<T>(map: &Map<T>, min: (f32, f32), max: (f32, f32)) -> Self {
+        let mut voronoi = Self {
+            min,
+            max,
+            sites: map.rooms.iter().map(|(id, r)| (*id, r.position)).collect(),
+            cells: BTreeMap::new(),
+        };
+        let ids: Vec<RoomId> = voronoi.sites.keys().copied().collect();
+        for id in ids {
+            let cell = voronoi.compute_cell(id);
+            voronoi.cells.insert(id, cell);
+        }
+        voronoi
+    }
+
+    pub fn cell(&self, room: RoomId) -> Option<&[(f32, f32)]> {
+        self.cells.get(&room).map(Vec::as_slice)
+    }
+
+    pub fn cells(&self) -> impl Iterator<Item = (RoomId, &[(f32, f32)])> {
+        self.cells.iter().map(|(id, cell)| (*id, cell.as_slice()))
+    }
+
+    pub fn area(&self, room: RoomId) -> Option<f32> {
+        self.cell(room).map(polygon_area)
+    }
+
+    /// Room whose territory contains `point`, the closest one, `None` outside of the bounds.
+    pub fn room_at(&self, point: (f32, f32)) -> Option<RoomId> {
+        let inside = point.0 >= self.min.0
+            && point.0 <= self.max.0
+            && point.1 >= self.min.1
+            && point.1 <= self.max.1;
+        if !inside {
+            return None;
+        }
+        self.sites
+            .iter()
+            .map(|(id, site)| (*id, poisson::distance_squared(site, &point)))
+            .min_by(|(_, d1), (_, d2)| d1.total_cmp(d2))
+            .map(|(id, _)| id)
+    }
+
+    /// Adds a room, or moves it, only clipping the cells it takes territory from.
+    pub fn insert(&mut self, room: RoomId, position: (f32, f32)) {
+        if self.sites.contains_key(&room) {
+            self.remove(room);
+        }
+        for (id, cell) in self.cells.iter_mut() {
+            *cell = clip(cell, self.sites[id], position);
+        }
+        self.sites.insert(room, position);
+        let cell = self.compute_cell(room);
+        self.cells.insert(room, cell);
+    }
+
+    /// Removes a room, its neighbours share its territory.
+    pub fn remove(&mut self, room: RoomId) -> bool {
+        self.sites.remove(&room);
+        let removed = match self.cells.remove(&room) {
+            Some(cell) => cell,
+            None => return false,
+        };
+        // Neighbours share an edge, so at least a vertex, with the removed cell.
+        let neighbours: Vec<RoomId> = self
+            .cells
+            .iter()
+            .filter(|(_, cell)| {
+                cell.iter().any(|v| {
+                    removed
+                        .iter()
+                        .any(|r| poisson::distance_squared(v, r) < VERTEX_EPSILON * VERTEX_EPSILON)
+                })
+            })
+            .map(|(id, _)| *id)
+            .collect();
+        for id in neighbours {
+            let cell = self.compute_cell(id);
+            self.cells.insert(id, cell);
+        }
+        true
+    }
+
+    /// Applies the rooms added, removed or moved in `map` since the last update.
+    pub fn update<T>(&mut self, map: &Map<T>) {
+        let removed: Vec<RoomId> = self
+            .sites
+            .keys()
+            .filter(|id| !map.rooms.contains_key(id))
+            .copied()
+            .collect();
+        for id in removed {
+            self.remove(id);
+        }
+        for id in map.sorted_ids() {
+            let position = map.rooms[&id].position;
+            if self.sites.get(&id) != Some(&position) {
+                self.insert(id, position);
+            }
+        }
+    }
+
+    fn compute_cell(&self, room: RoomId) -> Vec<(f32, f32)> {
+        let site = self.sites[&room];
+        let mut cell = vec![
+            self.min,
+            (self.max.0, self.min.1),
+            self.max,
+            (self.min.0, self.max.1),
+        ];
+        for (id, other) in self.sites.iter() {
+            if *id != room {
+                cell = clip(&cell, site, *other);
+            }
+        }
+        cell
+    }
+}
+
+/// Part of `polygon` closer to `site` than to `other`, Sutherland-Hodgman style.
+fn clip(polygon: &[(f32, f32)], site: (f32, f32), other: (f32, f32)) -> Vec<(f32, f32)> {
+    let normal = (other.0 - site.0, other.1 - site.1);
+    if normal == (0f32, 0f32) {
+        // Rooms on the same spot can't be told apart, they share the cell.
+        return polygon.to_vec();
+    }
+    let middle = ((site.0 + other.0) / 2f32, (site.1 + other.1) / 2f32);
+    // Negative on the side of `site`.
+    let side = |p: (f32, f32)| (p.0 - middle.0) * normal.0 + (p.1 - middle.1) * normal.1;
+
+    let mut clipped = Vec::with_capacity(polygon.len() + 1);
+    for (i, current) in polygon.iter().enumerate() {
+        let next = polygon[(i + 1) % polygon.len()];
+        let (current_side, next_side) = (side(*current), side(next));
+        if current_side <= 0f32 {
+            clipped.push(*current);
+        }
+        if (current_side < 0f32 && next_side > 0f32) || (current_side > 0f32 && next_side < 0f32) {
+            let t = current_side / (current_side - next_side);
+            clipped.push((
+                current.0 + (next.0 - current.0) * t,
+                current.1 + (next.1 - current.1) * t,
+            ));
+        }
+    }
+    clipped
+}
+
+/// Shoelace formula, positive for counter-clockwise polygons.
+fn polygon_area(polygon: &[(f32, f32)]) -> f32 {
+    let doubled: f32 = polygon
+        .iter()
+        .enumerate()
+        .map(|(i, a)| {
+            let b = polygon[(i + 1) % polygon.len()];
+            a.0 * b.1 - b.0 * a.1
+        })
+        .sum();
+    doubled / 2f32
+}
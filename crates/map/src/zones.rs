@@ -0,0 +1,336 @@
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+
+use rand::{seq::SliceRandom, Rng};
+use serde::{Deserialize, Serialize};
+
+use crate::{metrics, pathfinding, Map, MapEvent, RoomId};
+
+const PALETTE: [[f32; 3]; 6] = [
+    [0.13, 0.55, 0.13],
+    [0.85, 0.65, 0.13],
+    [0.27, 0.51, 0.71],
+    [0.70, 0.13, 0.13],
+    [0.58, 0.44, 0.86],
+    [0.50, 0.50, 0.50],
+];
+
+#[derive(
+    PartialOrd, Ord, PartialEq, Eq, Hash, Default, Clone, Copy, Debug, Serialize, Deserialize,
+)]
+pub struct ZoneId(usize);
+
+/// A named group of rooms, such as a biome or a district.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Zone {
+    pub name: String,
+    /// Linear rgb, each channel in `0..=1`.
+    pub color: [f32; 3],
+}
+
+/// Zone layer over the rooms of a map, every room belongs to at most one zone.
+///
+/// Zone adjacency is kept up to date from the connections of the map: call [`Zones::sync`] once,
+/// then [`Zones::apply_events`] with the changes the map records, see [`Map::record_events`].
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct Zones {
+    pub zones: BTreeMap<ZoneId, Zone>,
+    room_zones: HashMap<RoomId, ZoneId>,
+    zone_id_provider: ZoneId,
+    /// Both ends of every connection, once per direction.
+    #[serde(skip)]
+    links: HashMap<RoomId, Vec<RoomId>>,
+    /// Connections between rooms of two zones, by `(lowest, highest)` zone, never 0.
+    #[serde(skip)]
+    crossings: BTreeMap<(ZoneId, ZoneId), usize>,
+}
+
+impl Zones {
+    pub fn add_zone(&mut self, zone: Zone) -> ZoneId {
+        let id = self.zone_id_provider;
+        self.zones.insert(id, zone);
+        self.zone_id_provider.0 += 1;
+        id
+    }
+
+    /// Zone with a generated name and a color from the default palette.
+    pub fn add_default_zone(&mut self) -> ZoneId {
+        let index = self.zone_id_provider.0;
+        self.add_zone(Zone {
+            name: format!("zone-{}", index),
+            color: PALETTE[index % PALETTE.len()],
+        })
+    }
+
+    pub fn assign(&mut self, room: RoomId, zone: ZoneId) {
+        let previous = self.room_zones.insert(room, zone);
+        self.move_crossings(room, previous, Some(zone));
+    }
+
+    pub fn unassign(&mut self, room: RoomId) -> Option<ZoneId> {
+        let previous = self.room_zones.remove(&room);
+        self.move_crossings(room, previous, None);
+        previous
+    }
+
+    pub fn zone_of(&self, room: RoomId) -> Option<ZoneId> {
+        self.room_zones.get(&room).copied()
+    }
+
+    pub fn rooms_in(&self, zone: ZoneId) -> Vec<RoomId> {
+        let mut rooms: Vec<RoomId> = self
+            .room_zones
+            .iter()
+            .filter(|(_, z)| **z == zone)
+            .map(|(room, _)| *room)
+            .collect();
+        rooms.sort();
+        rooms
+    }
+
+    /// Mirrors every connection of `map`, after creating or loading the zones.
+    pub fn sync<T>(&mut self, map: &Map<T>) {
+        self.links.clear();
+        self.crossings.clear();
+        for from in map.sorted_ids() {
+            for to in map.rooms[&from].connections.iter() {
+                self.link(from, *to);
+            }
+        }
+    }
+
+    /// Follows the connection changes of the map, rooms removed from it are unassigned.
+    pub fn apply_events<'a>(&mut self, events: impl IntoIterator<Item = &'a MapEvent>) {
+        for event in events {
+            match *event {
+                MapEvent::EdgeAdded(from, to) => self.link(from, to),
+                MapEvent::EdgeRemoved(from, to) => self.unlink(from, to),
+                MapEvent::RoomRemoved(room) => {
+                    self.unassign(room);
+                    self.links.remove(&room);
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn link(&mut self, from: RoomId, to: RoomId) {
+        self.links.entry(from).or_default().push(to);
+        self.links.entry(to).or_default().push(from);
+        self.count_crossing(self.zone_of(from), self.zone_of(to), 1);
+    }
+
+    fn unlink(&mut self, from: RoomId, to: RoomId) {
+        for (a, b) in [(from, to), (to, from)] {
+            if let Some(links) = self.links.get_mut(&a) {
+                if let Some(index) = links.iter().position(|room| *room == b) {
+                    links.swap_remove(index);
+                }
+            }
+        }
+        self.count_crossing(self.zone_of(from), self.zone_of(to), -1);
+    }
+
+    /// Moves the connections of `room` from the crossings of `from` to the ones of `to`.
+    fn move_crossings(&mut self, room: RoomId, from: Option<ZoneId>, to: Option<ZoneId>) {
+        if from == to {
+            return;
+        }
+        let links = self.links.get(&room).cloned().unwrap_or_default();
+        for other in links.into_iter().filter(|other| *other != room) {
+            let other_zone = self.zone_of(other);
+            self.count_crossing(from, other_zone, -1);
+            self.count_crossing(to, other_zone, 1);
+        }
+    }
+
+    fn count_crossing(&mut self, a: Option<ZoneId>, b: Option<ZoneId>, change: isize) {
+        let (a, b) = match (a, b) {
+            (Some(a), Some(b)) if a != b => (a.min(b), a.max(b)),
+            _ => return,
+        };
+        let count = self.crossings.entry((a, b)).or_default();
+        *count = count.saturating_add_signed(change);
+        if *count == 0 {
+            self.crossings.remove(&(a, b));
+        }
+    }
+
+    /// Zones linked by at least one connection between their rooms.
+    pub fn zone_graph(&self) -> BTreeMap<ZoneId, Vec<ZoneId>> {
+        let mut graph: BTreeMap<ZoneId, Vec<ZoneId>> =
+            self.zones.keys().map(|id| (*id, vec![])).collect();
+        for (a, b) in self.crossings.keys() {
+            graph.entry(*a).or_default().push(*b);
+            graph.entry(*b).or_default().push(*a);
+        }
+        for neighbours in graph.values_mut() {
+            neighbours.sort();
+        }
+        graph
+    }
+
+    pub fn adjacent_zones(&self, zone: ZoneId) -> Vec<ZoneId> {
+        let mut adjacent: Vec<ZoneId> = self
+            .crossings
+            .keys()
+            .filter_map(|(a, b)| {
+                if *a == zone {
+                    Some(*b)
+                } else if *b == zone {
+                    Some(*a)
+                } else {
+                    None
+                }
+            })
+            .collect();
+        adjacent.sort();
+        adjacent
+    }
+
+    pub fn are_adjacent(&self, a: ZoneId, b: ZoneId) -> bool {
+        self.crossings.contains_key(&(a.min(b), a.max(b)))
+    }
+
+    /// Plans over zones first, then searches rooms only inside the zones of that plan.
+    ///
+    /// Falls back to a search over the whole map when the zone plan can't be followed,
+    /// which happens with zones whose rooms aren't connected together, or with unzoned rooms.
+    pub fn find_path<T>(&self, map: &Map<T>, from: RoomId, to: RoomId) -> Option<Vec<RoomId>> {
+        let (zone_from, zone_to) = match (self.zone_of(from), self.zone_of(to)) {
+            (Some(zone_from), Some(zone_to)) => (zone_from, zone_to),
+            _ => return pathfinding::shortest_path(map, from, to),
+        };
+        let corridor: HashSet<ZoneId> = match self.zone_path(zone_from, zone_to) {
+            Some(zone_path) => zone_path.into_iter().collect(),
+            None => return pathfinding::shortest_path(map, from, to),
+        };
+        pathfinding::shortest_path_within(map, from, to, |room| {
+            self.zone_of(room).is_some_and(|z| corridor.contains(&z))
+        })
+        .or_else(|| pathfinding::shortest_path(map, from, to))
+    }
+
+    /// Fewest zone crossings from `from` to `to`.
+    pub fn zone_path(&self, from: ZoneId, to: ZoneId) -> Option<Vec<ZoneId>> {
+        let mut came_from: HashMap<ZoneId, ZoneId> = HashMap::new();
+        let mut queue = VecDeque::from([from]);
+        let mut visited = HashSet::from([from]);
+        while let Some(current) = queue.pop_front() {
+            if current == to {
+                let mut path = vec![to];
+                while let Some(previous) = came_from.get(path.last().unwrap()) {
+                    path.push(*previous);
+                }
+                path.reverse();
+                return Some(path);
+            }
+            for next in self.adjacent_zones(current) {
+                if visited.insert(next) {
+                    came_from.insert(next, current);
+                    queue.push_back(next);
+                }
+            }
+        }
+        None
+    }
+
+    /// Graph clustering: every room repeatedly takes the most common zone among its neighbours.
+    pub fn label_propagation<T>(map: &Map<T>, rng: &mut impl Rng, max_iterations: usize) -> Self {
+        let adjacency = metrics::undirected_adjacency(map);
+        let mut order = map.sorted_ids();
+        let mut labels: HashMap<RoomId, RoomId> = order.iter().map(|id| (*id, *id)).collect();
+        for _ in 0..max_iterations {
+            order.shuffle(rng);
+            let mut changed = false;
+            for room in order.iter() {
+                let mut counts: BTreeMap<RoomId, usize> = BTreeMap::new();
+                for neighbour in adjacency[room].iter() {
+                    *counts.entry(labels[neighbour]).or_default() += 1;
+                }
+                let best = match counts.values().max() {
+                    Some(best) => *best,
+                    None => continue,
+                };
+                let candidates: Vec<RoomId> = counts
+                    .into_iter()
+                    .filter(|(_, count)| *count == best)
+                    .map(|(label, _)| label)
+                    .collect();
+                if candidates.contains(&labels[room]) {
+                    continue;
+                }
+                labels.insert(*room, *candidates.choose(rng).unwrap());
+                changed = true;
+            }
+            if !changed {
+                break;
+            }
+        }
+        Self::from_labels(map, |room| labels[&room])
+    }
+
+    /// Spatial clustering of room positions into at most `k` zones, no zones for `k == 0`.
+    ///
+    /// Rooms are always assigned once, even with `max_iterations == 0`.
+    pub fn k_means<T>(map: &Map<T>, k: usize, rng: &mut impl Rng, max_iterations: usize) -> Self {
+        if k == 0 {
+            let mut zones = Self::default();
+            zones.sync(map);
+            return zones;
+        }
+        let ids = map.sorted_ids();
+        let mut centers: Vec<(f32, f32)> = ids
+            .choose_multiple(rng, k.min(ids.len()))
+            .map(|id| map.rooms[id].position)
+            .collect();
+        let mut assignment: HashMap<RoomId, usize> = HashMap::new();
+        for _ in 0..max_iterations.max(1) {
+            let mut changed = false;
+            for id in ids.iter() {
+                let position = map.rooms[id].position;
+                let closest = (0..centers.len())
+                    .min_by(|a, b| {
+                        poisson::distance_squared(&position, &centers[*a])
+                            .total_cmp(&poisson::distance_squared(&position, &centers[*b]))
+                    })
+                    .unwrap();
+                if assignment.insert(*id, closest) != Some(closest) {
+                    changed = true;
+                }
+            }
+            if !changed {
+                break;
+            }
+            for (i, center) in centers.iter_mut().enumerate() {
+                let members: Vec<(f32, f32)> = ids
+                    .iter()
+                    .filter(|id| assignment[id] == i)
+                    .map(|id| map.rooms[id].position)
+                    .collect();
+                if members.is_empty() {
+                    continue;
+                }
+                let count = members.len() as f32;
+                *center = (
+                    members.iter().map(|p| p.0).sum::<f32>() / count,
+                    members.iter().map(|p| p.1).sum::<f32>() / count,
+                );
+            }
+        }
+        Self::from_labels(map, |room| assignment[&room])
+    }
+
+    /// One zone per distinct label, numbered in order of their lowest room.
+    fn from_labels<T, L: Eq + std::hash::Hash>(map: &Map<T>, label: impl Fn(RoomId) -> L) -> Self {
+        let mut zones = Zones::default();
+        let mut label_zones: HashMap<L, ZoneId> = HashMap::new();
+        for room in map.sorted_ids() {
+            let zone = *label_zones
+                .entry(label(room))
+                .or_insert_with(|| zones.add_default_zone());
+            zones.assign(room, zone);
+        }
+        zones.sync(map);
+        zones
+    }
+}
@@ -0,0 +1,128 @@
+mod common;
+
+use map::{
+    chunks::{ChunkConfig, ChunkCoord, ChunkedWorld},
+    Map, ROOM_SPACING,
+};
+
+fn world(seed: u64) -> ChunkedWorld {
+    ChunkedWorld::new(ChunkConfig {
+        world_seed: seed,
+        ..Default::default()
+    })
+}
+
+fn sorted_positions(map: &Map<i32>) -> Vec<(f32, f32)> {
+    let mut positions: Vec<(f32, f32)> = map.rooms.values().map(|r| r.position).collect();
+    positions.sort_by(|a, b| a.0.total_cmp(&b.0).then(a.1.total_cmp(&b.1)));
+    positions
+}
+
+/// Connections as the positions of their rooms, ids depend on the load order.
+fn sorted_edges(map: &Map<i32>) -> Vec<[f32; 4]> {
+    let compare = |a: &[f32], b: &[f32]| {
+        a.iter()
+            .zip(b)
+            .map(|(x, y)| x.total_cmp(y))
+            .find(|ordering| ordering.is_ne())
+            .unwrap_or(std::cmp::Ordering::Equal)
+    };
+    let mut edges: Vec<[f32; 4]> = map
+        .edges()
+        .into_iter()
+        .map(|(a, b)| {
+            let (a, b) = (map.rooms[&a].position, map.rooms[&b].position);
+            match compare(&[a.0, a.1], &[b.0, b.1]).is_le() {
+                true => [a.0, a.1, b.0, b.1],
+                false => [b.0, b.1, a.0, a.1],
+            }
+        })
+        .collect();
+    edges.sort_by(|a, b| compare(a, b));
+    edges
+}
+
+#[test]
+fn chunks_do_not_depend_on_load_order() {
+    let chunks = [
+        ChunkCoord::new(0, 0),
+        ChunkCoord::new(1, 0),
+        ChunkCoord::new(0, -1),
+        ChunkCoord::new(1, -1),
+        ChunkCoord::new(-1, 0),
+        ChunkCoord::new(-1, 1),
+    ];
+
+    let (mut first, mut first_map) = (world(7), Map::default());
+    for chunk in chunks {
+        assert!(first.load_chunk(&mut first_map, chunk, 0));
+    }
+    let (mut second, mut second_map) = (world(7), Map::default());
+    for chunk in chunks.iter().rev() {
+        assert!(second.load_chunk(&mut second_map, *chunk, 0));
+    }
+
+    assert!(!first_map.is_empty());
+    assert_eq!(sorted_positions(&first_map), sorted_positions(&second_map));
+    assert_eq!(sorted_edges(&first_map), sorted_edges(&second_map));
+    // Loading a chunk after its neighbours gives the same world too.
+    let (mut third, mut third_map) = (world(7), Map::default());
+    for chunk in [
+        chunks[1], chunks[3], chunks[0], chunks[5], chunks[2], chunks[4],
+    ] {
+        assert!(third.load_chunk(&mut third_map, chunk, 0));
+    }
+    assert_eq!(sorted_edges(&first_map), sorted_edges(&third_map));
+    assert_ne!(first.chunk_seed(chunks[0]), world(8).chunk_seed(chunks[0]));
+}
+
+#[test]
+fn chunks_are_stitched_and_spaced() {
+    let (mut world, mut map) = (world(3), Map::default());
+    let changes = world.update(&mut map, (150f32, 150f32), &[], 0);
+    assert_eq!(changes.loaded.len(), 9);
+    assert!(changes.unloaded.is_empty());
+
+    assert!(common::dangling_edges(&map).is_empty());
+    assert!(common::closest_rooms_distance(&map).unwrap() >= ROOM_SPACING - 0.01f32);
+    let size = world.config.chunk_size;
+    for (id, room) in map.rooms.iter() {
+        assert_eq!(
+            world.chunk_of(*id),
+            Some(ChunkCoord::containing(room.position, size))
+        );
+    }
+    let crossing = map
+        .edges()
+        .into_iter()
+        .filter(|(a, b)| world.chunk_of(*a) != world.chunk_of(*b))
+        .count();
+    assert!(crossing > 0);
+}
+
+#[test]
+fn unloading_keeps_pinned_chunks_and_never_reuses_ids() {
+    let (mut world, mut map) = (world(11), Map::default());
+    world.update(&mut map, (150f32, 150f32), &[], 0);
+    let origin = ChunkCoord::new(0, 0);
+    let pinned = world.rooms_in(origin)[0];
+    let far_away = world.rooms_in(ChunkCoord::new(-1, -1))[0];
+    let highest = map.sorted_ids().last().copied().unwrap();
+
+    let changes = world.update(&mut map, (3000f32, 150f32), &[pinned], 0);
+    assert_eq!(changes.loaded.len(), 9);
+    assert_eq!(changes.unloaded.len(), 8);
+    assert!(!changes.unloaded.contains(&origin));
+    assert!(world.is_loaded(origin));
+    assert!(map.rooms.contains_key(&pinned));
+    assert!(!map.rooms.contains_key(&far_away));
+    assert!(world.chunk_of(far_away).is_none());
+    assert!(common::dangling_edges(&map).is_empty());
+    for chunk in changes.loaded {
+        assert!(world.rooms_in(chunk).iter().all(|id| *id > highest));
+    }
+
+    let changes = world.update(&mut map, (3000f32, 150f32), &[], 0);
+    assert_eq!(changes.unloaded, vec![origin]);
+    assert!(!map.rooms.contains_key(&pinned));
+}
@@ -0,0 +1,48 @@
+#![allow(dead_code)]
+
+use map::{
+    generator::{Generator, GeneratorConfig},
+    Map, RoomId,
+};
+use rand::{rngs::StdRng, SeedableRng};
+
+/// Every test randomness goes through this, so a failure can be replayed from its seed.
+pub fn seeded_rng(seed: u64) -> StdRng {
+    StdRng::seed_from_u64(seed)
+}
+
+pub fn generated_map(seed: u64, rooms: usize) -> Map<i32> {
+    let mut rng = seeded_rng(seed);
+    let mut map = Map::default();
+    Generator::new(GeneratorConfig {
+        rooms,
+        ..Default::default()
+    })
+    .generate(&mut map, 0, &mut rng);
+    map
+}
+
+/// Connections pointing to a room which is not in the map.
+pub fn dangling_edges<T>(map: &Map<T>) -> Vec<(RoomId, RoomId)> {
+    let mut dangling = vec![];
+    for (id, room) in map.rooms.iter() {
+        for c in room.connections.iter() {
+            if !map.rooms.contains_key(c) {
+                dangling.push((*id, *c));
+            }
+        }
+    }
+    dangling
+}
+
+pub fn closest_rooms_distance<T>(map: &Map<T>) -> Option<f32> {
+    let positions: Vec<(f32, f32)> = map.rooms.values().map(|r| r.position).collect();
+    let mut closest: Option<f32> = None;
+    for (i, p1) in positions.iter().enumerate() {
+        for p2 in positions.iter().skip(i + 1) {
+            let distance = poisson::distance_squared(p1, p2).sqrt();
+            closest = Some(closest.map_or(distance, |c| c.min(distance)));
+        }
+    }
+    closest
+}
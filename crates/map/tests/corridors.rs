@@ -0,0 +1,121 @@
+mod common;
+
+use map::{
+    corridors::{self, Corridor, CorridorConfig},
+    Map,
+};
+use rand::Rng;
+
+fn segment_distance(a: (f32, f32), b: (f32, f32), point: (f32, f32)) -> f32 {
+    let (dx, dy) = (b.0 - a.0, b.1 - a.1);
+    let t = (((point.0 - a.0) * dx + (point.1 - a.1) * dy) / (dx * dx + dy * dy)).clamp(0f32, 1f32);
+    let closest = (a.0 + dx * t, a.1 + dy * t);
+    poisson::distance_squared(&closest, &point).sqrt()
+}
+
+fn corridor_distance(corridor: &Corridor, point: (f32, f32)) -> f32 {
+    corridor
+        .points
+        .windows(2)
+        .map(|s| segment_distance(s[0], s[1], point))
+        .fold(f32::INFINITY, f32::min)
+}
+
+#[test]
+fn corridors_go_around_rooms() {
+    let mut map = Map::default();
+    let a = map.create_raw(0, (0f32, 0f32), vec![]);
+    let blocking = map.create_raw(0, (50f32, 0f32), vec![]);
+    let b = map.create_raw(0, (100f32, 0f32), vec![]);
+    map.connect(a, b).unwrap();
+    let config = CorridorConfig::default();
+
+    let corridor = corridors::route(&map, a, b, &config).unwrap();
+    assert_eq!(corridor.points.first(), Some(&(0f32, 0f32)));
+    assert_eq!(corridor.points.last(), Some(&(100f32, 0f32)));
+    assert!(corridor.points.len() > 2);
+    let clearance = corridor_distance(&corridor, map.rooms[&blocking].position);
+    assert!(clearance >= config.room_radius + config.clearance - 0.01f32);
+
+    // Nothing in the way: a straight line.
+    map.remove(blocking);
+    let straight = corridors::route(&map, a, b, &config).unwrap();
+    assert_eq!(straight.points, vec![(0f32, 0f32), (100f32, 0f32)]);
+    assert_eq!(straight.length(), 100f32);
+    assert_eq!(straight.point_at(0.25f32), (25f32, 0f32));
+    assert_eq!(straight.reversed().point_at(0.25f32), (75f32, 0f32));
+}
+
+#[test]
+fn corridors_of_generated_maps_keep_clearance() {
+    for (seed, smoothing) in [(9, 0), (9, 3), (4, 3), (12, 5)] {
+        let map = common::generated_map(seed, 30);
+        let config = CorridorConfig {
+            smoothing,
+            ..Default::default()
+        };
+        let all = corridors::route_all(&map, &config);
+        assert_eq!(all.len(), map.edges().len());
+        for ((from, to), corridor) in all.iter() {
+            assert!(from < to);
+            assert_eq!(corridor.points.first(), Some(&map.rooms[from].position));
+            assert_eq!(corridor.points.last(), Some(&map.rooms[to].position));
+            let straight =
+                poisson::distance_squared(&map.rooms[from].position, &map.rooms[to].position)
+                    .sqrt();
+            assert!(corridor.length() >= straight - 0.01f32);
+            for (id, room) in map.rooms.iter() {
+                if id == from || id == to {
+                    continue;
+                }
+                let clearance = corridor_distance(corridor, room.position);
+                assert!(
+                    clearance >= config.room_radius + config.clearance - 0.01f32,
+                    "seed {}, smoothing {}: corridor {:?} passes {} from room {:?}",
+                    seed,
+                    smoothing,
+                    (from, to),
+                    clearance,
+                    id
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn smoothed_detours_keep_clearance() {
+    // Rooms scattered across a long corridor force detours whose spline could cut corners.
+    for seed in 0..100 {
+        let mut rng = common::seeded_rng(seed);
+        let mut map = Map::default();
+        let a = map.create_raw(0, (0f32, 0f32), vec![]);
+        let b = map.create_raw(0, (300f32, 0f32), vec![]);
+        map.connect(a, b).unwrap();
+        for _ in 0..4 {
+            let position = (rng.gen_range(40f32..260f32), rng.gen_range(-40f32..40f32));
+            if map
+                .rooms
+                .values()
+                .all(|room| poisson::distance_squared(&room.position, &position) > 45f32 * 45f32)
+            {
+                map.create_raw(0, position, vec![]);
+            }
+        }
+        let config = CorridorConfig {
+            smoothing: 4,
+            ..Default::default()
+        };
+        let corridor = corridors::route(&map, a, b, &config).unwrap();
+        for (id, room) in map.rooms.iter() {
+            if *id != a && *id != b {
+                let clearance = corridor_distance(&corridor, room.position);
+                assert!(
+                    clearance >= config.room_radius + config.clearance - 0.01f32,
+                    "seed {}",
+                    seed
+                );
+            }
+        }
+    }
+}
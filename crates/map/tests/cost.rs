@@ -0,0 +1,178 @@
+use map::{cost, pathfinding, EdgeData, ErrorAdd, Map, RoomId};
+
+fn connect_both(map: &mut Map<i32>, a: RoomId, b: RoomId) {
+    map.connect(a, b).unwrap();
+    map.connect(b, a).unwrap();
+}
+
+/// A square: 0 at the origin, 1 and 2 100 units away, 3 opposite to 0.
+fn square() -> (Map<i32>, Vec<RoomId>) {
+    let mut map = Map::default();
+    let ids: Vec<RoomId> = [
+        (0f32, 0f32),
+        (100f32, 0f32),
+        (0f32, 100f32),
+        (100f32, 100f32),
+    ]
+    .into_iter()
+    .map(|position| map.create_raw(0, position, vec![]))
+    .collect();
+    for (a, b) in [(0, 1), (1, 3), (0, 2), (2, 3)] {
+        connect_both(&mut map, ids[a], ids[b]);
+    }
+    (map, ids)
+}
+
+#[test]
+fn travel_time_uses_terrain_and_doors() {
+    let (mut map, ids) = square();
+    assert_eq!(cost::travel_time(&map, ids[0], ids[1], 50f32), Some(2f32));
+
+    let data = EdgeData {
+        terrain: 3f32,
+        door_time: 1.5f32,
+    };
+    map.set_edge_data(ids[0], ids[1], data).unwrap();
+    assert_eq!(map.edge_data(ids[0], ids[1]), data);
+    assert_eq!(map.edge_data(ids[1], ids[0]), EdgeData::default());
+    assert_eq!(cost::travel_time(&map, ids[0], ids[1], 50f32), Some(7.5f32));
+    assert_eq!(
+        cost::path_travel_time(&map, &[ids[0], ids[1], ids[3]], 50f32),
+        Some(9.5f32)
+    );
+
+    assert!(matches!(
+        map.set_edge_data(ids[0], ids[3], data),
+        Err(ErrorAdd::NotConnected(..))
+    ));
+    for invalid in [
+        EdgeData {
+            terrain: -1f32,
+            door_time: 0f32,
+        },
+        EdgeData {
+            terrain: 1f32,
+            door_time: -0.5f32,
+        },
+        EdgeData {
+            terrain: f32::NAN,
+            door_time: 0f32,
+        },
+    ] {
+        assert!(matches!(
+            map.set_edge_data(ids[0], ids[1], invalid),
+            Err(ErrorAdd::InvalidEdgeData(..))
+        ));
+    }
+    assert_eq!(map.edge_data(ids[0], ids[1]), data);
+    map.disconnect(ids[0], ids[1]);
+    assert_eq!(map.edge_data(ids[0], ids[1]), EdgeData::default());
+    map.set_edge_data(ids[2], ids[3], data).unwrap();
+    map.remove(ids[3]);
+    assert_eq!(map.iter_edge_data().count(), 0);
+}
+
+#[test]
+fn pathfinding_agrees_with_travel_time() {
+    let (mut map, ids) = square();
+    map.set_edge_data(
+        ids[0],
+        ids[1],
+        EdgeData {
+            terrain: 1f32,
+            door_time: 1f32,
+        },
+    )
+    .unwrap();
+
+    map.set_edge_data(
+        ids[0],
+        ids[2],
+        EdgeData {
+            terrain: 1.5f32,
+            door_time: 0f32,
+        },
+    )
+    .unwrap();
+
+    // Slow units barely notice the door, fast ones go the long way around it.
+    let (slow_path, slow_time) = pathfinding::fastest_path(&map, ids[0], ids[3], 10f32).unwrap();
+    assert_eq!(slow_path, vec![ids[0], ids[1], ids[3]]);
+    assert_eq!(
+        Some(slow_time),
+        cost::path_travel_time(&map, &slow_path, 10f32)
+    );
+    let (fast_path, fast_time) = pathfinding::fastest_path(&map, ids[0], ids[3], 1000f32).unwrap();
+    assert_eq!(fast_path, vec![ids[0], ids[2], ids[3]]);
+    assert_eq!(
+        Some(fast_time),
+        cost::path_travel_time(&map, &fast_path, 1000f32)
+    );
+
+    map.set_edge_data(
+        ids[0],
+        ids[2],
+        EdgeData {
+            terrain: 5f32,
+            door_time: 0f32,
+        },
+    )
+    .unwrap();
+    let path = pathfinding::shortest_path(&map, ids[0], ids[3]).unwrap();
+    assert_eq!(path, vec![ids[0], ids[1], ids[3]]);
+}
+
+#[test]
+fn pathfinding_follows_given_lengths() {
+    let (map, ids) = square();
+    // The corridor from 0 to 1 makes a long detour.
+    let length = |from: RoomId, to: RoomId| {
+        let straight = cost::edge_length(&map, from, to).unwrap();
+        if [from, to] == [ids[0], ids[1]] || [from, to] == [ids[1], ids[0]] {
+            straight * 3f32
+        } else {
+            straight
+        }
+    };
+    let (path, time) =
+        pathfinding::fastest_path_along(&map, ids[0], ids[3], 10f32, |_, _| true, length).unwrap();
+    assert_eq!(path, vec![ids[0], ids[2], ids[3]]);
+    assert_eq!(time, 20f32);
+    assert_eq!(
+        cost::travel_time_over(&map, ids[0], ids[1], length(ids[0], ids[1]), 10f32),
+        30f32
+    );
+
+    // Only following some connections, as the AI does with the ones it discovered.
+    let known = |from: RoomId, to: RoomId| [from, to] != [ids[0], ids[2]];
+    let (path, time) =
+        pathfinding::fastest_path_along(&map, ids[0], ids[3], 10f32, known, length).unwrap();
+    assert_eq!(path, vec![ids[0], ids[1], ids[3]]);
+    assert_eq!(time, 40f32);
+    assert_eq!(
+        pathfinding::fastest_path_along(&map, ids[0], ids[2], 10f32, known, length)
+            .map(|(path, _)| path),
+        Some(vec![ids[0], ids[1], ids[3], ids[2]])
+    );
+}
+
+#[test]
+fn edge_data_survives_serialization() {
+    let (mut map, ids) = square();
+    let data = EdgeData {
+        terrain: 2f32,
+        door_time: 0.5f32,
+    };
+    map.set_edge_data(ids[1], ids[3], data).unwrap();
+
+    let json = serde_json::to_string(&map).unwrap();
+    let loaded: Map<i32> = serde_json::from_str(&json).unwrap();
+    assert_eq!(loaded.edge_data(ids[1], ids[3]), data);
+
+    // Maps saved before edge data existed still load.
+    let (plain, _) = square();
+    let json = serde_json::to_string(&plain).unwrap();
+    assert!(!json.contains("edge_data"));
+    let loaded: Map<i32> = serde_json::from_str(&json).unwrap();
+    assert_eq!(loaded.iter_edge_data().count(), 0);
+}
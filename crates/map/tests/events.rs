@@ -0,0 +1,129 @@
+mod common;
+
+use map::{
+    generator::{Generator, GeneratorConfig},
+    layout::ForceLayout,
+    EdgeData, Map, MapEvent,
+};
+
+#[test]
+fn mutations_are_recorded_in_order() {
+    let mut map = Map::default();
+    map.record_events(true);
+    let a = map.create_raw(0, (0f32, 0f32), vec![]);
+    let b = map.create_raw(0, (50f32, 0f32), vec![a]);
+    map.connect(a, b).unwrap();
+    map.connect(a, b).unwrap();
+    assert!(map.set_position(a, (-10f32, 0f32)));
+    assert!(map.set_position(a, (-10f32, 0f32)));
+    // Only recorded once until drained.
+    assert!(map.set_position(a, (-20f32, 0f32)));
+    assert_eq!(map.set_data(b, 3), Some(0));
+    assert_eq!(map.set_data(b, 4), Some(3));
+    let door = EdgeData {
+        terrain: 1f32,
+        door_time: 2f32,
+    };
+    map.set_edge_data(a, b, door).unwrap();
+    map.set_edge_data(a, b, door).unwrap();
+    assert_eq!(
+        map.drain_events().collect::<Vec<_>>(),
+        vec![
+            MapEvent::RoomAdded(a),
+            MapEvent::RoomAdded(b),
+            MapEvent::EdgeAdded(b, a),
+            MapEvent::EdgeAdded(a, b),
+            MapEvent::RoomMoved(a),
+            MapEvent::DataChanged(b),
+            MapEvent::EdgeDataChanged(a, b),
+        ]
+    );
+    assert!(map.events().is_empty());
+    assert!(map.set_position(a, (-10f32, 0f32)));
+    assert_eq!(map.events(), &[MapEvent::RoomMoved(a)]);
+
+    let c = map.create_raw(0, (0f32, 50f32), vec![]);
+    map.connect(c, a).unwrap();
+    assert!(map.disconnect(b, a));
+    assert!(!map.disconnect(b, a));
+    map.drain_events();
+    map.remove(a);
+    assert_eq!(
+        map.drain_events().collect::<Vec<_>>(),
+        vec![
+            MapEvent::EdgeRemoved(a, b),
+            MapEvent::EdgeRemoved(c, a),
+            MapEvent::RoomRemoved(a),
+        ]
+    );
+}
+
+#[test]
+fn recording_is_opt_in() {
+    let mut map = common::generated_map(2, 10);
+    assert!(!map.is_recording_events());
+    assert!(map.events().is_empty());
+
+    map.record_events(true);
+    let id = map.sorted_ids()[0];
+    map.set_position(id, (1000f32, 1000f32));
+    // Pending changes belong to the consumers of the original map.
+    let copy = map.clone();
+    assert!(copy.events().is_empty());
+    assert!(copy.is_recording_events());
+    assert_eq!(map.events(), &[MapEvent::RoomMoved(id)]);
+    map.record_events(false);
+    assert!(map.events().is_empty());
+}
+
+#[test]
+fn algorithms_record_their_changes() {
+    let mut map = Map::default();
+    map.record_events(true);
+    Generator::new(GeneratorConfig {
+        rooms: 10,
+        ..Default::default()
+    })
+    .generate(&mut map, 0, &mut common::seeded_rng(2));
+    let added = map
+        .drain_events()
+        .filter(|e| matches!(e, MapEvent::RoomAdded(_)))
+        .count();
+    assert_eq!(added, map.len());
+
+    let mut other = common::generated_map(3, 5);
+    other.drain_events();
+    let mapping = map.merge(other, (500f32, 0f32), None);
+    let events: Vec<MapEvent> = map.drain_events().collect();
+    // Edges are only recorded between rooms already added.
+    for (i, event) in events.iter().enumerate() {
+        if let MapEvent::EdgeAdded(from, to) = event {
+            for room in [from, to] {
+                assert!(events[..i].contains(&MapEvent::RoomAdded(*room)));
+            }
+        }
+    }
+    assert_eq!(
+        events
+            .iter()
+            .filter(|e| matches!(e, MapEvent::RoomAdded(_)))
+            .count(),
+        mapping.len()
+    );
+
+    ForceLayout::new(Default::default()).run(&mut map, &mut common::seeded_rng(0));
+    let moves: Vec<MapEvent> = map.drain_events().collect();
+    assert!(moves.iter().all(|e| matches!(e, MapEvent::RoomMoved(_))));
+    // Once per room, however many iterations ran.
+    assert!(moves.len() <= map.len());
+
+    // Pending changes are not part of the saved map.
+    map.connect(
+        mapping.values().next().copied().unwrap(),
+        map.sorted_ids()[0],
+    )
+    .unwrap();
+    let json = serde_json::to_string(&map).unwrap();
+    let loaded: Map<i32> = serde_json::from_str(&json).unwrap();
+    assert!(loaded.events().is_empty());
+}
@@ -0,0 +1,86 @@
+mod common;
+
+use map::{
+    fingerprint::{self, Fingerprint, FingerprintConfig},
+    Map, RoomId,
+};
+
+fn connect_both(map: &mut Map<i32>, a: RoomId, b: RoomId) {
+    map.connect(a, b).unwrap();
+    map.connect(b, a).unwrap();
+}
+
+/// Rooms on a line, created in `order`, with edges between consecutive indices plus `extra`.
+fn built(order: &[usize], extra: &[(usize, usize)], offset: (f32, f32)) -> Map<i32> {
+    let mut map = Map::default();
+    let mut ids = vec![RoomId::default(); order.len()];
+    for index in order {
+        let position = (*index as f32 * 50f32 + offset.0, offset.1);
+        ids[*index] = map.create_raw(0, position, vec![]);
+    }
+    for i in 1..order.len() {
+        connect_both(&mut map, ids[i - 1], ids[i]);
+    }
+    for (a, b) in extra {
+        connect_both(&mut map, ids[*a], ids[*b]);
+    }
+    map
+}
+
+#[test]
+fn isomorphic_maps_share_fingerprints() {
+    let config = FingerprintConfig::default();
+    let map = built(&[0, 1, 2, 3, 4], &[(0, 2)], (0f32, 0f32));
+    let shuffled = built(&[3, 0, 4, 2, 1], &[(0, 2)], (130f32, -70f32));
+
+    let a = Fingerprint::compute(&map, &config);
+    let b = Fingerprint::compute(&shuffled, &config);
+    assert_eq!(a, b);
+    assert!(a.is_duplicate_of(&b));
+    assert_eq!(a.similarity(&b), 1f32);
+}
+
+#[test]
+fn different_maps_are_told_apart() {
+    let config = FingerprintConfig::default();
+    let line = Fingerprint::compute(&built(&[0, 1, 2, 3, 4], &[], (0f32, 0f32)), &config);
+    let cycle = Fingerprint::compute(&built(&[0, 1, 2, 3, 4], &[(0, 4)], (0f32, 0f32)), &config);
+    let closer = Fingerprint::compute(&built(&[0, 1, 2, 3, 4], &[(0, 2)], (0f32, 0f32)), &config);
+    assert_ne!(line.topology, cycle.topology);
+    assert!(!line.is_duplicate_of(&cycle));
+
+    let similarity = line.similarity(&closer);
+    assert!(similarity > 0f32 && similarity < 1f32);
+    assert!(similarity > line.similarity(&cycle));
+
+    // Same topology, one room moved by several cells.
+    let mut moved = built(&[0, 1, 2, 3, 4], &[], (0f32, 0f32));
+    let last = moved.sorted_ids()[4];
+    moved.rooms.get_mut(&last).unwrap().position.1 += 100f32;
+    let moved = Fingerprint::compute(&moved, &config);
+    assert_eq!(moved.topology, line.topology);
+    assert_ne!(moved.geometry, line.geometry);
+}
+
+#[test]
+fn fingerprints_are_stable() {
+    let map = common::generated_map(5, 30);
+    let fingerprint = Fingerprint::compute(&map, &FingerprintConfig::default());
+    assert_eq!(
+        fingerprint,
+        Fingerprint::compute(&map.clone(), &Default::default())
+    );
+    assert_eq!(fingerprint.topology, fingerprint::topology_hash(&map, 3));
+    assert_eq!(fingerprint::similarity(&map, &map, 3), 1f32);
+
+    // Stored fingerprints must keep matching across releases.
+    let triangle = built(&[0, 1, 2], &[(0, 2)], (0f32, 0f32));
+    assert_eq!(
+        fingerprint::topology_hash(&triangle, 2),
+        14682437125746137007
+    );
+    assert_eq!(
+        fingerprint::geometric_hash(&triangle, 20f32),
+        3154379311015888195
+    );
+}
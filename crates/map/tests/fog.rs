@@ -0,0 +1,101 @@
+use map::{
+    fog::{Faction, FogOfWar},
+    Map, RoomId,
+};
+
+/// Rooms in a line, connected both ways.
+fn line(length: usize) -> (Map<i32>, Vec<RoomId>) {
+    let mut map = Map::default();
+    let mut ids: Vec<RoomId> = vec![];
+    for i in 0..length {
+        let connections = ids.last().copied().into_iter().collect();
+        let id = map.create_raw(0, (i as f32 * 50f32, 0f32), connections);
+        if let Some(previous) = ids.last() {
+            map.connect(*previous, id).unwrap();
+        }
+        ids.push(id);
+    }
+    (map, ids)
+}
+
+const PLAYER: Faction = Faction(0);
+const ENEMY: Faction = Faction(1);
+
+#[test]
+fn vision_spreads_along_connections() {
+    let (map, ids) = line(6);
+    let mut fog = FogOfWar::new(2);
+    fog.update(&map, PLAYER, [ids[2]]);
+    let knowledge = fog.knowledge(PLAYER).unwrap();
+    assert_eq!(
+        knowledge.visible_rooms().collect::<Vec<_>>(),
+        ids[0..5].to_vec()
+    );
+    assert!(knowledge.is_edge_visible(ids[3], ids[4]));
+    assert!(!knowledge.is_edge_visible(ids[4], ids[5]));
+    assert!(!knowledge.is_discovered(ids[5]));
+    assert!(fog.knowledge(ENEMY).is_none());
+
+    fog.update(&map, ENEMY, [ids[5]]);
+    let enemy = fog.knowledge(ENEMY).unwrap();
+    assert_eq!(
+        enemy.visible_rooms().collect::<Vec<_>>(),
+        ids[3..6].to_vec()
+    );
+    assert!(!fog.knowledge(PLAYER).unwrap().is_discovered(ids[5]));
+}
+
+#[test]
+fn discovered_rooms_are_remembered() {
+    let (mut map, ids) = line(5);
+    let mut fog = FogOfWar::new(1);
+    fog.update(&map, PLAYER, [ids[0]]);
+    fog.update(&map, PLAYER, [ids[4]]);
+    let knowledge = fog.knowledge(PLAYER).unwrap();
+    assert!(knowledge.is_discovered(ids[1]) && !knowledge.is_visible(ids[1]));
+    assert!(!knowledge.is_discovered(ids[2]));
+    assert_eq!(knowledge.known_path(ids[0], ids[4]), None);
+    // The connection to the undiscovered room is not known either.
+    assert_eq!(
+        knowledge.known_connections(ids[3]).collect::<Vec<_>>(),
+        vec![ids[4]]
+    );
+
+    fog.update(&map, PLAYER, [ids[2]]);
+    assert_eq!(
+        fog.knowledge(PLAYER).unwrap().known_path(ids[0], ids[4]),
+        Some(ids.clone())
+    );
+
+    // Out of sight, removed connections are still known but removed rooms are forgotten.
+    map.disconnect(ids[0], ids[1]);
+    fog.update(&map, PLAYER, [ids[4]]);
+    assert!(fog
+        .knowledge(PLAYER)
+        .unwrap()
+        .is_edge_discovered(ids[0], ids[1]));
+    map.remove(ids[1]);
+    fog.update(&map, PLAYER, [ids[4]]);
+    let knowledge = fog.knowledge(PLAYER).unwrap();
+    assert!(!knowledge.is_discovered(ids[1]));
+    assert!(!knowledge.is_edge_discovered(ids[0], ids[1]));
+    assert!(!knowledge.is_edge_discovered(ids[2], ids[1]));
+}
+
+#[test]
+fn connections_removed_in_sight_are_forgotten() {
+    let (mut map, ids) = line(3);
+    let mut fog = FogOfWar::new(1);
+    fog.update(&map, PLAYER, [ids[1]]);
+    map.disconnect(ids[1], ids[2]);
+    fog.update(&map, PLAYER, [ids[1]]);
+    let knowledge = fog.knowledge(PLAYER).unwrap();
+    assert!(!knowledge.is_edge_discovered(ids[1], ids[2]));
+    // The way back is only seen from the other room.
+    assert!(knowledge.is_edge_discovered(ids[2], ids[1]));
+    assert!(!knowledge.is_visible(ids[2]));
+    assert_eq!(
+        knowledge.known_connections(ids[1]).collect::<Vec<_>>(),
+        vec![ids[0]]
+    );
+}
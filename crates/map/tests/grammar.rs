@@ -0,0 +1,116 @@
+mod common;
+
+use map::{
+    grammar::{Grammar, GrammarError},
+    Map,
+};
+use serde::Deserialize;
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+enum Label {
+    Start,
+    Corridor,
+    SideRoom,
+}
+
+const RULES: &str = r#"
+(
+    rules: [
+        (
+            name: "start",
+            lhs: (nodes: [Start]),
+            rhs: (
+                nodes: [(label: Start, keep: Some(0)), (label: Corridor)],
+                edges: [(0, 1)],
+            ),
+        ),
+        (
+            name: "side_room",
+            lhs: (nodes: [Corridor, Corridor], edges: [(0, 1)]),
+            rhs: (
+                nodes: [
+                    (label: Corridor, keep: Some(0)),
+                    (label: SideRoom),
+                    (label: Corridor, keep: Some(1)),
+                ],
+                edges: [(0, 1), (1, 2)],
+            ),
+            weight: 3,
+        ),
+        (
+            name: "extend",
+            lhs: (nodes: [Corridor]),
+            rhs: (
+                nodes: [(label: Corridor, keep: Some(0)), (label: Corridor)],
+                edges: [(0, 1)],
+            ),
+        ),
+    ],
+)
+"#;
+
+fn start_map() -> Map<Label> {
+    let mut map = Map::default();
+    map.create_raw(Label::Start, (0f32, 0f32), vec![]);
+    map
+}
+
+#[test]
+fn rewrites_are_bounded_and_valid() {
+    let grammar: Grammar<Label> = Grammar::from_ron(RULES).unwrap();
+    let mut map = start_map();
+    let rewrites = grammar.apply(&mut map, &mut common::seeded_rng(0), 12);
+    assert_eq!(rewrites, 12);
+    assert!(map.len() > 2);
+    assert!(common::dangling_edges(&map).is_empty());
+    assert_eq!(
+        map.iter().filter(|(_, r)| r.data == Label::Start).count(),
+        1
+    );
+    assert_eq!(map::metrics::component_count(&map), 1);
+    // Side rooms are only ever created between two corridors.
+    for (_, room) in map.iter().filter(|(_, r)| r.data == Label::SideRoom) {
+        assert!(room
+            .connections
+            .iter()
+            .all(|c| map.rooms[c].data == Label::Corridor));
+    }
+}
+
+#[test]
+fn rewrites_are_deterministic() {
+    let grammar: Grammar<Label> = Grammar::from_ron(RULES).unwrap();
+    let mut map_1 = start_map();
+    let mut map_2 = start_map();
+    grammar.apply(&mut map_1, &mut common::seeded_rng(7), 20);
+    grammar.apply(&mut map_2, &mut common::seeded_rng(7), 20);
+    assert_eq!(map_1.edges(), map_2.edges());
+    for (id, room) in map_1.iter() {
+        assert_eq!(room.position, map_2.rooms[id].position);
+        assert_eq!(room.data, map_2.rooms[id].data);
+    }
+}
+
+#[test]
+fn matches_respect_labels_and_edges() {
+    let grammar: Grammar<Label> = Grammar::from_ron(RULES).unwrap();
+    let mut map = start_map();
+    assert_eq!(grammar.find_matches(&map).len(), 1);
+    grammar.apply_once(&mut map, &mut common::seeded_rng(0));
+    // `start` again, `extend` on the new corridor, no pair of corridors yet.
+    let rules: Vec<usize> = grammar.find_matches(&map).iter().map(|m| m.rule).collect();
+    assert_eq!(rules, vec![0, 2]);
+}
+
+#[test]
+fn invalid_rules_are_rejected() {
+    let invalid = r#"(rules: [(name: "bad", lhs: (nodes: [Start]), rhs: (nodes: [(label: Start, keep: Some(1))]))])"#;
+    assert!(matches!(
+        Grammar::<Label>::from_ron(invalid),
+        Err(GrammarError::InvalidNodeIndex { index: 1, .. })
+    ));
+    assert!(matches!(
+        Grammar::<Label>::from_ron("(rules: [(name: 3)])"),
+        Err(GrammarError::Parse(_))
+    ));
+}
@@ -1,19 +1,20 @@
+mod common;
+
 use map::Map;
 
 #[test]
 fn add() {
     let mut map = Map::default();
     let mut room_id = map.create_raw(0, (0f32, 0f32), vec![]);
-    let mut rng = rand::thread_rng();
+    let mut rng = common::seeded_rng(0);
 
     let add_res = map.add(room_id, 1, &mut rng, 1);
     assert!(add_res.is_ok(), "second room creation must always succeed");
     room_id = add_res.unwrap();
 
-    // TODO: this has a small chance of failure.., I should not be dependent on rng for tests.
     assert!(
         map.add(room_id, 1, &mut rng, 5).is_ok(),
-        "third room creation is very likely to succeed"
+        "third room creation succeeds with this seed"
     );
 
     let mut has_failed = false;
@@ -27,6 +28,16 @@ fn add() {
     }
     assert!(
         has_failed,
-        "Adding a lot of rooms has a very high chance of failure"
+        "Adding a lot of rooms around the same room eventually fails"
     );
 }
+
+#[test]
+fn generation_is_deterministic() {
+    let map_1 = common::generated_map(42, 25);
+    let map_2 = common::generated_map(42, 25);
+    assert_eq!(map_1.edges(), map_2.edges());
+    for (id, room) in map_1.iter() {
+        assert_eq!(room.position, map_2.rooms[id].position);
+    }
+}
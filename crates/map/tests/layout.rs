@@ -0,0 +1,89 @@
+mod common;
+
+use map::{
+    layout::{ForceLayout, LayoutConfig},
+    Map, ROOM_SPACING,
+};
+
+fn edge_length(map: &Map<i32>, a: map::RoomId, b: map::RoomId) -> f32 {
+    poisson::distance_squared(&map.rooms[&a].position, &map.rooms[&b].position).sqrt()
+}
+
+#[test]
+fn relaxation_keeps_spacing() {
+    for seed in 0..20 {
+        let mut map = common::generated_map(seed, 25);
+        ForceLayout::new(LayoutConfig::default()).run(&mut map, &mut common::seeded_rng(seed));
+        let closest = common::closest_rooms_distance(&map).unwrap();
+        assert!(closest >= ROOM_SPACING - 0.01, "seed {}: {}", seed, closest);
+    }
+}
+
+#[test]
+fn dense_clusters_are_spread() {
+    use rand::Rng;
+
+    for seed in 0..10 {
+        let mut rng = common::seeded_rng(seed);
+        let mut map = Map::default();
+        let mut previous = None;
+        for i in 0..40 {
+            // Every fifth room shares the position of the previous one.
+            let position = match previous {
+                Some(position) if i % 5 == 0 => position,
+                _ => (rng.gen_range(0f32..5f32), rng.gen_range(0f32..5f32)),
+            };
+            let room = map.create_raw(0, position, vec![]);
+            if i > 0 {
+                map.connect(room, map::RoomId::default()).unwrap();
+            }
+            previous = Some(position);
+        }
+        let mut layout = ForceLayout::new(LayoutConfig {
+            iterations: 5,
+            ..Default::default()
+        });
+        while layout.step(&mut map, &mut rng) {
+            let closest = common::closest_rooms_distance(&map).unwrap();
+            assert!(closest >= ROOM_SPACING - 0.01, "seed {}: {}", seed, closest);
+        }
+        let closest = common::closest_rooms_distance(&map).unwrap();
+        assert!(closest >= ROOM_SPACING - 0.01, "seed {}: {}", seed, closest);
+    }
+}
+
+#[test]
+fn stretched_edges_get_shorter() {
+    let mut map = Map::default();
+    let a = map.create_raw(0, (0f32, 0f32), vec![]);
+    let b = map.create_raw(0, (1000f32, 0f32), vec![]);
+    let c = map.create_raw(0, (0f32, 0f32), vec![]);
+    for (x, y) in [(a, b), (b, c)] {
+        map.connect(x, y).unwrap();
+        map.connect(y, x).unwrap();
+    }
+    let mut layout = ForceLayout::new(LayoutConfig {
+        iterations: 200,
+        ..Default::default()
+    });
+    let mut rng = common::seeded_rng(0);
+    assert!(layout.step(&mut map, &mut rng));
+    // `a` and `c` shared a position and were pushed apart.
+    assert!(edge_length(&map, a, c) >= ROOM_SPACING - 0.01);
+    layout.run(&mut map, &mut rng);
+    assert!(layout.is_done());
+    assert!(!layout.step(&mut map, &mut rng));
+    assert!(edge_length(&map, a, b) < 200f32);
+    assert!(edge_length(&map, b, c) < 200f32);
+}
+
+#[test]
+fn relaxation_is_deterministic() {
+    let mut map_1 = common::generated_map(3, 25);
+    let mut map_2 = common::generated_map(3, 25);
+    ForceLayout::new(LayoutConfig::default()).run(&mut map_1, &mut common::seeded_rng(1));
+    ForceLayout::new(LayoutConfig::default()).run(&mut map_2, &mut common::seeded_rng(1));
+    for (id, room) in map_1.iter() {
+        assert_eq!(room.position, map_2.rooms[id].position);
+    }
+}
@@ -0,0 +1,66 @@
+mod common;
+
+use map::{
+    lock_and_key::{self, LockAndKey, LockError},
+    Map,
+};
+
+#[test]
+fn generated_layouts_are_solvable() {
+    for seed in 0..100 {
+        let map = common::generated_map(seed, 25);
+        let start = map.sorted_ids()[0];
+        let layout = match lock_and_key::generate(&map, start, 3, &mut common::seeded_rng(seed), 10)
+        {
+            Ok(layout) => layout,
+            Err(LockError::NotEnoughEdges { .. }) => continue,
+            Err(e) => panic!("seed {}: {}", seed, e),
+        };
+        assert_eq!(layout.locks.len(), 3);
+        assert_eq!(layout.keys.len(), 3);
+        let order = layout.solve(&map).expect("generated layouts are solvable");
+        assert_eq!(order.len(), 3);
+        for (a, b) in layout.locks.keys() {
+            assert!(map.rooms[a].connections.contains(b) || map.rooms[b].connections.contains(a));
+        }
+    }
+}
+
+#[test]
+fn key_behind_its_own_lock_is_unsolvable() {
+    // start - a - b, the only way to `b` is locked and its key is in `b`.
+    let mut map = Map::default();
+    let start = map.create_raw(0, (0f32, 0f32), vec![]);
+    let a = map.create_raw(0, (50f32, 0f32), vec![]);
+    let b = map.create_raw(0, (100f32, 0f32), vec![]);
+    for (x, y) in [(start, a), (a, b)] {
+        map.connect(x, y).unwrap();
+        map.connect(y, x).unwrap();
+    }
+    let layout = lock_and_key::generate(&map, start, 1, &mut common::seeded_rng(0), 10).unwrap();
+    let (edge, key) = layout.locks.iter().next().map(|(e, k)| (*e, *k)).unwrap();
+
+    let mut broken = LockAndKey {
+        start,
+        ..Default::default()
+    };
+    broken.locks.insert(edge, key);
+    broken.keys.insert(key, edge.1);
+    assert!(!broken.is_solvable(&map));
+    assert!(layout.is_solvable(&map));
+}
+
+#[test]
+fn errors() {
+    let map = common::generated_map(0, 3);
+    let start = map.sorted_ids()[0];
+    let mut rng = common::seeded_rng(0);
+    assert_eq!(
+        lock_and_key::generate(&map, start, 65, &mut rng, 1).unwrap_err(),
+        LockError::TooManyLocks(65)
+    );
+    assert!(matches!(
+        lock_and_key::generate(&map, start, 10, &mut rng, 1).unwrap_err(),
+        LockError::NotEnoughEdges { needed: 10, .. }
+    ));
+}
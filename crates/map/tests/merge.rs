@@ -0,0 +1,66 @@
+mod common;
+
+use map::Map;
+
+#[test]
+fn merge_remaps_ids_and_translates() {
+    let mut map = common::generated_map(1, 10);
+    let other = common::generated_map(2, 10);
+    let len = map.len();
+    let other_edges = other.edges().len();
+    let edges = map.edges().len();
+
+    let offset = (1000f32, 0f32);
+    let mapping = map.merge(other.clone(), offset, None);
+
+    assert_eq!(map.len(), len + other.len());
+    assert_eq!(map.edges().len(), edges + other_edges);
+    assert!(common::dangling_edges(&map).is_empty());
+    for (old, new) in mapping.iter() {
+        let (x, y) = other.rooms[old].position;
+        assert_eq!(map.rooms[new].position, (x + offset.0, y + offset.1));
+        let mut expected: Vec<_> = other.rooms[old]
+            .connections
+            .iter()
+            .map(|c| mapping[c])
+            .collect();
+        expected.sort();
+        let mut connections = map.rooms[new].connections.clone();
+        connections.sort();
+        assert_eq!(connections, expected);
+    }
+    assert_eq!(map::metrics::component_count(&map), 2);
+}
+
+#[test]
+fn merge_stitches_facing_rooms() {
+    let mut left = Map::default();
+    let a = left.create_raw(0, (0f32, 0f32), vec![]);
+    let b = left.create_raw(0, (50f32, 0f32), vec![]);
+    left.connect(a, b).unwrap();
+    left.connect(b, a).unwrap();
+    let right = left.subgraph(&[a, b]);
+
+    let mapping = left.merge(right, (100f32, 0f32), Some(60f32));
+    // Only `b` at x = 50 and the moved `a` at x = 100 face each other.
+    assert!(left.rooms[&b].connections.contains(&mapping[&a]));
+    assert!(left.rooms[&mapping[&a]].connections.contains(&b));
+    assert!(!left.rooms[&a].connections.contains(&mapping[&a]));
+    assert_eq!(left.edges().len(), 3);
+    assert_eq!(map::metrics::component_count(&left), 1);
+}
+
+#[test]
+fn subgraph_keeps_ids_and_drops_outside_edges() {
+    let map = common::generated_map(3, 20);
+    let ids: Vec<_> = map.sorted_ids().into_iter().take(5).collect();
+    let region = map.subgraph(&ids);
+    assert_eq!(region.sorted_ids(), ids);
+    assert!(common::dangling_edges(&region).is_empty());
+    for id in ids.iter() {
+        assert_eq!(region.rooms[id].position, map.rooms[id].position);
+        for c in region.rooms[id].connections.iter() {
+            assert!(map.rooms[id].connections.contains(c));
+        }
+    }
+}
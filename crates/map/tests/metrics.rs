@@ -0,0 +1,67 @@
+use map::{metrics, metrics::MapMetrics, Map, RoomId};
+
+fn connect_both(map: &mut Map<i32>, a: RoomId, b: RoomId) {
+    map.connect(a, b).unwrap();
+    map.connect(b, a).unwrap();
+}
+
+/// 0 - 1 - 2 - 3, and a triangle 4 - 5 - 6 with 5 also linked to 3.
+fn path_and_triangle() -> (Map<i32>, Vec<RoomId>) {
+    let mut map = Map::default();
+    let ids: Vec<RoomId> = (0..7)
+        .map(|i| map.create_raw(0, (i as f32 * 50f32, 0f32), vec![]))
+        .collect();
+    for (a, b) in [(0, 1), (1, 2), (2, 3), (3, 5), (4, 5), (5, 6), (6, 4)] {
+        connect_both(&mut map, ids[a], ids[b]);
+    }
+    (map, ids)
+}
+
+#[test]
+fn metrics_on_known_graph() {
+    let (map, ids) = path_and_triangle();
+    let report = MapMetrics::compute(&map);
+    assert_eq!(report.rooms, 7);
+    assert_eq!(report.edges, 7);
+    assert_eq!(report.components, 1);
+    assert_eq!(report.diameter, 5);
+    assert_eq!(report.dead_ends, 1);
+    assert_eq!(report.degree_distribution.get(&1), Some(&1));
+    assert_eq!(report.degree_distribution.get(&2), Some(&5));
+    assert_eq!(report.degree_distribution.get(&3), Some(&1));
+
+    // Only 4, 5 and 6 have connected neighbours: 1 + 1/3 + 1.
+    let expected_clustering = (1f32 + 1f32 / 3f32 + 1f32) / 7f32;
+    assert!((report.clustering_coefficient - expected_clustering).abs() < 1e-5);
+
+    // Room 3 splits {0, 1, 2} from {4, 5, 6}: 9 pairs, counted in both directions.
+    let betweenness = metrics::betweenness_centrality(&map);
+    assert_eq!(betweenness[&ids[3]], 18f32);
+    assert_eq!(betweenness[&ids[5]], 16f32);
+    assert_eq!(betweenness[&ids[0]], 0f32);
+    assert_eq!(report.max_betweenness, 18f32);
+}
+
+#[test]
+fn average_shortest_path_of_a_line() {
+    let mut map = Map::default();
+    let a = map.create_raw(0, (0f32, 0f32), vec![]);
+    let b = map.create_raw(0, (50f32, 0f32), vec![]);
+    let c = map.create_raw(0, (100f32, 0f32), vec![]);
+    connect_both(&mut map, a, b);
+    connect_both(&mut map, b, c);
+    // Ordered pairs: 4 at distance 1, 2 at distance 2.
+    assert!((metrics::average_shortest_path(&map) - 8f32 / 6f32).abs() < 1e-5);
+    assert_eq!(metrics::betweenness_centrality(&map)[&b], 2f32);
+}
+
+#[test]
+fn components_ignore_isolated_direction() {
+    let mut map = Map::default();
+    let a = map.create_raw(0, (0f32, 0f32), vec![]);
+    let b = map.create_raw(0, (50f32, 0f32), vec![]);
+    map.create_raw(0, (100f32, 0f32), vec![]);
+    map.connect(a, b).unwrap();
+    assert_eq!(metrics::component_count(&map), 2);
+    assert_eq!(metrics::diameter(&map), 1);
+}
@@ -0,0 +1,161 @@
+mod common;
+
+use map::{
+    placement::{self, Constraint, PlacementError, PlacementRequest},
+    Map, RoomId,
+};
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+enum Role {
+    Player,
+    Ai,
+    Pickup,
+}
+
+/// A star: a center room with `branches` arms of `length` rooms each.
+fn star(branches: usize, length: usize) -> (Map<i32>, RoomId) {
+    let mut map = Map::default();
+    let center = map.create_raw(0, (0f32, 0f32), vec![]);
+    for b in 0..branches {
+        let mut previous = center;
+        for l in 1..=length {
+            let angle = b as f32 / branches as f32 * std::f32::consts::TAU;
+            let distance = l as f32 * 50f32;
+            let room = map.create_raw(0, (angle.cos() * distance, angle.sin() * distance), vec![]);
+            map.connect(previous, room).unwrap();
+            map.connect(room, previous).unwrap();
+            previous = room;
+        }
+    }
+    (map, center)
+}
+
+#[test]
+fn constraints_are_respected() {
+    // Generated maps are connected and large enough, every seed has a placement.
+    for seed in 0..200 {
+        let map = common::generated_map(seed, 25);
+        let request = PlacementRequest::new(
+            vec![(Role::Player, 1), (Role::Ai, 2), (Role::Pickup, 4)],
+            vec![
+                Constraint::MinHops {
+                    role: Role::Pickup,
+                    from: Role::Player,
+                    hops: 3,
+                },
+                Constraint::MinHops {
+                    role: Role::Ai,
+                    from: Role::Player,
+                    hops: 4,
+                },
+            ],
+        );
+        let placement = placement::place(&map, &request, &mut common::seeded_rng(seed))
+            .unwrap_or_else(|e| panic!("seed {}: {}", seed, e));
+        let player = placement.rooms_of(&Role::Player)[0];
+        let distances = map.distances_from(player);
+        assert_eq!(placement.rooms_of(&Role::Pickup).len(), 4);
+        for pickup in placement.rooms_of(&Role::Pickup) {
+            assert!(distances[pickup] >= 3, "seed {}", seed);
+        }
+        for ai in placement.rooms_of(&Role::Ai) {
+            assert!(distances[ai] >= 4, "seed {}", seed);
+            assert_eq!(placement.role_of(*ai), Some(&Role::Ai));
+        }
+    }
+}
+
+#[test]
+fn distinct_branches() {
+    let (map, center) = star(4, 3);
+    let request = PlacementRequest::new(
+        vec![(Role::Player, 1), (Role::Pickup, 4)],
+        vec![
+            Constraint::MinHops {
+                role: Role::Pickup,
+                from: Role::Player,
+                hops: 2,
+            },
+            Constraint::DistinctBranches {
+                role: Role::Pickup,
+                root: Role::Player,
+            },
+        ],
+    );
+    let placement = placement::place(&map, &request, &mut common::seeded_rng(1)).unwrap();
+    let player = placement.rooms_of(&Role::Player)[0];
+    let branches = placement::branches_from(&map, player);
+    let mut used: Vec<RoomId> = placement
+        .rooms_of(&Role::Pickup)
+        .iter()
+        .map(|p| branches[p])
+        .collect();
+    used.sort();
+    used.dedup();
+    assert_eq!(used.len(), 4);
+    // Only the center has 4 branches.
+    assert_eq!(player, center);
+}
+
+#[test]
+fn impossible_constraints_are_reported() {
+    let (map, _) = star(3, 2);
+    let too_many_branches = PlacementRequest::new(
+        vec![(Role::Player, 1), (Role::Pickup, 4)],
+        vec![Constraint::DistinctBranches {
+            role: Role::Pickup,
+            root: Role::Player,
+        }],
+    );
+    assert_eq!(
+        placement::place(&map, &too_many_branches, &mut common::seeded_rng(0)).unwrap_err(),
+        PlacementError::Unsatisfiable
+    );
+
+    let too_many_rooms = PlacementRequest::new(vec![(Role::Pickup, 8)], vec![]);
+    assert_eq!(
+        placement::place(&map, &too_many_rooms, &mut common::seeded_rng(0)).unwrap_err(),
+        PlacementError::NotEnoughRooms {
+            needed: 8,
+            available: 7
+        }
+    );
+}
+
+#[test]
+fn min_hops_needs_a_path() {
+    // Two connected rooms, and one out of reach.
+    let mut map = Map::default();
+    let a = map.create_raw(0, (0f32, 0f32), vec![]);
+    let b = map.create_raw(0, (50f32, 0f32), vec![]);
+    map.create_raw(0, (500f32, 0f32), vec![]);
+    map.connect(a, b).unwrap();
+    map.connect(b, a).unwrap();
+    let request = PlacementRequest::new(
+        vec![(Role::Player, 1), (Role::Pickup, 1)],
+        vec![Constraint::MinHops {
+            role: Role::Pickup,
+            from: Role::Player,
+            hops: 1,
+        }],
+    );
+    for seed in 0..20 {
+        let placement = placement::place(&map, &request, &mut common::seeded_rng(seed)).unwrap();
+        let player = placement.rooms_of(&Role::Player)[0];
+        let pickup = placement.rooms_of(&Role::Pickup)[0];
+        assert_eq!(
+            map.distances_from(player).get(&pickup),
+            Some(&1),
+            "seed {}",
+            seed
+        );
+    }
+
+    let mut isolated = Map::default();
+    isolated.create_raw(0, (0f32, 0f32), vec![]);
+    isolated.create_raw(0, (500f32, 0f32), vec![]);
+    assert_eq!(
+        placement::place(&isolated, &request, &mut common::seeded_rng(0)).unwrap_err(),
+        PlacementError::Unsatisfiable
+    );
+}
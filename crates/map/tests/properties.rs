@@ -0,0 +1,79 @@
+mod common;
+
+use map::{Map, ROOM_SPACING};
+use proptest::prelude::*;
+use rand::Rng;
+
+// f32 rounding of the poisson placement.
+const SPACING_TOLERANCE: f32 = 0.001f32;
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(2000))]
+
+    #[test]
+    fn generated_maps_are_valid(seed in any::<u64>(), rooms in 1usize..30) {
+        let map = common::generated_map(seed, rooms);
+        prop_assert!(!map.is_empty());
+        prop_assert!(common::dangling_edges(&map).is_empty());
+        if let Some(closest) = common::closest_rooms_distance(&map) {
+            prop_assert!(closest >= ROOM_SPACING - SPACING_TOLERANCE, "rooms at {}", closest);
+        }
+    }
+
+    #[test]
+    fn add_keeps_spacing_and_connects_both_ways(seed in any::<u64>(), count in 1usize..20) {
+        let mut rng = common::seeded_rng(seed);
+        let mut map = Map::default();
+        let mut ids = vec![map.create_raw(0, (0f32, 0f32), vec![])];
+        for _ in 0..count {
+            let from = ids[rng.gen_range(0..ids.len())];
+            if let Ok(id) = map.add(from, 0, &mut rng, 10) {
+                prop_assert!(map.rooms[&from].connections.contains(&id));
+                prop_assert!(map.rooms[&id].connections.contains(&from));
+                ids.push(id);
+            }
+        }
+        let closest = common::closest_rooms_distance(&map).unwrap_or(ROOM_SPACING);
+        prop_assert!(closest >= ROOM_SPACING - SPACING_TOLERANCE, "rooms at {}", closest);
+    }
+
+    #[test]
+    fn connect_only_links_existing_rooms(seed in any::<u64>(), rooms in 2usize..20) {
+        let mut rng = common::seeded_rng(seed);
+        let mut map = common::generated_map(seed, rooms);
+        let ids = map.sorted_ids();
+        let removed = ids[rng.gen_range(0..ids.len())];
+        map.remove(removed);
+        for _ in 0..10 {
+            let from = ids[rng.gen_range(0..ids.len())];
+            let to = ids[rng.gen_range(0..ids.len())];
+            let res = map.connect(from, to);
+            prop_assert_eq!(res.is_ok(), from != removed && to != removed);
+        }
+        prop_assert!(common::dangling_edges(&map).is_empty());
+        for room in map.rooms.values() {
+            let mut connections = room.connections.clone();
+            connections.sort();
+            connections.dedup();
+            prop_assert_eq!(connections.len(), room.connections.len());
+        }
+    }
+
+    #[test]
+    fn remove_leaves_no_dangling_edges(seed in any::<u64>(), rooms in 1usize..30, removals in 1usize..10) {
+        let mut rng = common::seeded_rng(seed);
+        let mut map = common::generated_map(seed, rooms);
+        for _ in 0..removals {
+            let ids = map.sorted_ids();
+            if ids.is_empty() {
+                break;
+            }
+            let id = ids[rng.gen_range(0..ids.len())];
+            let len = map.len();
+            prop_assert!(map.remove(id).is_some());
+            prop_assert!(map.remove(id).is_none());
+            prop_assert_eq!(map.len(), len - 1);
+        }
+        prop_assert!(common::dangling_edges(&map).is_empty());
+    }
+}
@@ -0,0 +1,125 @@
+mod common;
+
+use std::collections::VecDeque;
+
+use map::{
+    raster::{self, CorridorStyle, Grid, RasterConfig, RoomShape, Tile},
+    Map,
+};
+
+/// Walkable cells reachable from `start`, moving in 4 directions.
+fn walkable_from(grid: &Grid<Tile>, start: (usize, usize)) -> usize {
+    let mut visited = vec![false; grid.width * grid.height];
+    let mut queue = VecDeque::from([start]);
+    visited[start.1 * grid.width + start.0] = true;
+    let mut count = 0;
+    while let Some((x, y)) = queue.pop_front() {
+        count += 1;
+        for (nx, ny) in [
+            (x + 1, y),
+            (x.wrapping_sub(1), y),
+            (x, y + 1),
+            (x, y.wrapping_sub(1)),
+        ] {
+            if grid.get(nx, ny).is_some_and(Tile::is_walkable) && !visited[ny * grid.width + nx] {
+                visited[ny * grid.width + nx] = true;
+                queue.push_back((nx, ny));
+            }
+        }
+    }
+    count
+}
+
+#[test]
+fn two_rooms_and_a_corridor() {
+    let mut map = Map::default();
+    let a = map.create_raw(0, (0f32, 0f32), vec![]);
+    let b = map.create_raw(1, (60f32, 40f32), vec![]);
+    map.connect(a, b).unwrap();
+    let shape = |data: &i32| match data {
+        0 => RoomShape::Rectangle {
+            width: 20f32,
+            height: 20f32,
+        },
+        _ => RoomShape::Circle { radius: 10f32 },
+    };
+
+    let grid = raster::rasterize(&map, &RasterConfig::default(), shape);
+    let ascii = grid.to_ascii();
+    assert_eq!(ascii.lines().count(), grid.height);
+    assert!(ascii.lines().all(|line| line.chars().count() == grid.width));
+    assert!(ascii.contains('.') && ascii.contains(',') && ascii.contains('#'));
+
+    let from = grid.cell_of((0f32, 0f32)).unwrap();
+    let to = grid.cell_of((60f32, 40f32)).unwrap();
+    assert_eq!(grid.get(from.0, from.1), Some(&Tile::Floor));
+    assert_eq!(grid.get(to.0, to.1), Some(&Tile::Floor));
+    let walkable = grid.iter().filter(|(_, tile)| tile.is_walkable()).count();
+    assert_eq!(walkable_from(&grid, from), walkable);
+    // Walls close everything: no walkable cell on the border.
+    assert!(grid
+        .iter()
+        .filter(|((x, y), _)| *x == 0 || *y == 0 || *x == grid.width - 1 || *y == grid.height - 1)
+        .all(|(_, tile)| !tile.is_walkable()));
+}
+
+#[test]
+fn bresenham_corridors_are_diagonal() {
+    let mut map = Map::default();
+    let a = map.create_raw(0, (0f32, 0f32), vec![]);
+    let b = map.create_raw(0, (100f32, 100f32), vec![]);
+    map.connect(a, b).unwrap();
+    let config = RasterConfig {
+        corridor_style: CorridorStyle::Bresenham,
+        walls: false,
+        ..Default::default()
+    };
+    let point = |_: &i32| RoomShape::Circle { radius: 0f32 };
+
+    let bresenham = raster::rasterize(&map, &config, point);
+    let l_shaped = raster::rasterize(
+        &map,
+        &RasterConfig {
+            corridor_style: CorridorStyle::LShaped,
+            ..config.clone()
+        },
+        point,
+    );
+    let walkable = |grid: &Grid<Tile>| grid.iter().filter(|(_, t)| t.is_walkable()).count();
+    assert_eq!(walkable(&bresenham), 11);
+    assert_eq!(walkable(&l_shaped), 21);
+}
+
+#[test]
+fn cell_size_scales_the_grid() {
+    let map = common::generated_map(4, 20);
+    let square = |_: &i32| RoomShape::Rectangle {
+        width: 30f32,
+        height: 30f32,
+    };
+    let coarse = raster::rasterize(&map, &RasterConfig::default(), square);
+    let fine = raster::rasterize(
+        &map,
+        &RasterConfig {
+            cell_size: 5f32,
+            ..Default::default()
+        },
+        square,
+    );
+    // Both cover the same extent, plus a cell of margin on each side and a partial cell.
+    let extent = |grid: &Grid<Tile>| (grid.width - 3, grid.height - 3);
+    let (coarse_extent, fine_extent) = (extent(&coarse), extent(&fine));
+    assert!(fine_extent.0.abs_diff(coarse_extent.0 * 2) <= 1);
+    assert!(fine_extent.1.abs_diff(coarse_extent.1 * 2) <= 1);
+    for room in map.rooms.values() {
+        let (x, y) = coarse.cell_of(room.position).unwrap();
+        assert_eq!(coarse.get(x, y), Some(&Tile::Floor));
+        let (x, y) = fine.cell_of(room.position).unwrap();
+        assert_eq!(fine.get(x, y), Some(&Tile::Floor));
+    }
+    let start = coarse
+        .cell_of(map.rooms.values().next().unwrap().position)
+        .unwrap();
+    let walkable = coarse.iter().filter(|(_, tile)| tile.is_walkable()).count();
+    assert_eq!(walkable_from(&coarse, start), walkable);
+}
@@ -0,0 +1,107 @@
+use map::{
+    generator::GeneratorConfig,
+    layout::LayoutConfig,
+    save::{SaveError, SaveFile, FORMAT_VERSION},
+    EdgeData, Map, RoomId,
+};
+
+const V1: &str = include_str!("fixtures/save_v1.json");
+const V2: &str = include_str!("fixtures/save_v2.json");
+const V3: &str = include_str!("fixtures/save_v3.json");
+
+/// Rooms of the fixtures: a corner of three rooms.
+fn assert_fixture_rooms(map: &Map<i32>) {
+    let ids = map.sorted_ids();
+    assert_eq!(ids.len(), 3);
+    let corner = &map.rooms[&ids[1]];
+    assert_eq!(corner.position, (40f32, 0f32));
+    assert_eq!(corner.data, 8);
+    assert!(corner.connections.contains(&ids[0]));
+    assert!(corner.connections.contains(&ids[2]));
+}
+
+#[test]
+fn every_version_loads_as_the_current_one() {
+    let v1: SaveFile<i32> = SaveFile::from_json(V1).unwrap();
+    assert_eq!(v1.format_version, FORMAT_VERSION);
+    assert_eq!(v1.generator, None);
+    assert_eq!(v1.seed, None);
+    assert_fixture_rooms(&v1.map);
+
+    let v2: SaveFile<i32> = SaveFile::from_json(V2).unwrap();
+    assert_eq!(v2.format_version, FORMAT_VERSION);
+    assert_eq!(v2.generator.as_deref(), Some("distance_growth"));
+    assert_eq!(v2.seed, Some(42));
+    assert_eq!(v2.config.unwrap().rooms, 3);
+    assert!(v2.layout.is_none());
+    assert_fixture_rooms(&v2.map);
+    let ids = v2.map.sorted_ids();
+    assert_eq!(
+        v2.map.edge_data(ids[1], ids[2]),
+        EdgeData {
+            terrain: 2f32,
+            door_time: 0.5f32
+        }
+    );
+
+    let v3: SaveFile<i32> = SaveFile::from_json(V3).unwrap();
+    assert_eq!(v3.seed, Some(42));
+    assert_eq!(v3.layout.unwrap().iterations, 20);
+    assert_fixture_rooms(&v3.map);
+
+    // Ids keep being provided after the loaded ones.
+    let mut map = v1.map;
+    let created = map.create_raw(0, (0f32, 30f32), vec![]);
+    assert!(!ids.contains(&created));
+}
+
+#[test]
+fn saves_round_trip() {
+    let mut map = Map::default();
+    let a = map.create_raw(1, (0f32, 0f32), vec![]);
+    let b = map.create_raw(2, (10f32, 5f32), vec![a]);
+    map.set_edge_data(
+        b,
+        a,
+        EdgeData {
+            terrain: 3f32,
+            door_time: 0f32,
+        },
+    )
+    .unwrap();
+    let save = SaveFile::generated(map, GeneratorConfig::default(), 7).with_layout(LayoutConfig {
+        iterations: 12,
+        ..Default::default()
+    });
+
+    let json = save.to_json().unwrap();
+    let loaded: SaveFile<i32> = SaveFile::from_json(&json).unwrap();
+    // Rooms are in a hash map, compare values rather than text.
+    let expected: serde_json::Value = serde_json::from_str(&json).unwrap();
+    let actual: serde_json::Value = serde_json::from_str(&loaded.to_json().unwrap()).unwrap();
+    assert_eq!(actual, expected);
+    assert_eq!(loaded.layout.unwrap().iterations, 12);
+    assert_eq!(loaded.map.edge_data(b, a).terrain, 3f32);
+    assert_eq!(loaded.map.rooms[&a].connections, Vec::<RoomId>::new());
+}
+
+#[test]
+fn unknown_saves_are_rejected() {
+    let newer = V3.replacen("\"format_version\": 3", "\"format_version\": 99", 1);
+    assert!(matches!(
+        SaveFile::<i32>::from_json(&newer),
+        Err(SaveError::UnsupportedVersion(99))
+    ));
+    assert!(matches!(
+        SaveFile::<i32>::from_json("[1, 2]"),
+        Err(SaveError::UnknownFormat)
+    ));
+    assert!(matches!(
+        SaveFile::<i32>::from_json(r#"{"format_version": 0, "map": {"rooms": {}}}"#),
+        Err(SaveError::UnknownFormat)
+    ));
+    assert!(matches!(
+        SaveFile::<i32>::from_json("{"),
+        Err(SaveError::Json(_))
+    ));
+}
@@ -0,0 +1,184 @@
+mod common;
+
+use map::{
+    undo::{Edit, EditError, UndoStack},
+    EdgeData, Map, RoomId,
+};
+
+/// Rooms, positions, data and connections, in a comparable form.
+type Snapshot = Vec<(RoomId, (f32, f32), i32, Vec<RoomId>)>;
+
+fn snapshot(map: &Map<i32>) -> Snapshot {
+    map.sorted_ids()
+        .into_iter()
+        .map(|id| {
+            let room = &map.rooms[&id];
+            let mut connections = room.connections.clone();
+            connections.sort();
+            (id, room.position, room.data, connections)
+        })
+        .collect()
+}
+
+#[test]
+fn every_edit_can_be_undone_and_redone() {
+    let mut map = common::generated_map(1, 10);
+    let ids = map.sorted_ids();
+    let (a, b) = (ids[0], ids[1]);
+    let door = EdgeData {
+        terrain: 1f32,
+        door_time: 2f32,
+    };
+    let first_connection = map.rooms[&a].connections[0];
+    map.set_edge_data(a, first_connection, door).unwrap();
+    let mut stack = UndoStack::new();
+
+    let mut snapshots = vec![snapshot(&map)];
+    let added = stack
+        .apply(
+            &mut map,
+            Edit::AddRoom {
+                data: 5,
+                position: (500f32, 500f32),
+            },
+        )
+        .unwrap();
+    snapshots.push(snapshot(&map));
+    stack.apply(&mut map, Edit::Connect(added, b)).unwrap();
+    snapshots.push(snapshot(&map));
+    stack
+        .apply(&mut map, Edit::MoveRoom(b, (-300f32, 0f32)))
+        .unwrap();
+    snapshots.push(snapshot(&map));
+    stack.apply(&mut map, Edit::SetData(b, 7)).unwrap();
+    snapshots.push(snapshot(&map));
+    stack.apply(&mut map, Edit::Disconnect(added, b)).unwrap();
+    snapshots.push(snapshot(&map));
+    stack.apply(&mut map, Edit::RemoveRoom(a)).unwrap();
+    snapshots.push(snapshot(&map));
+
+    for expected in snapshots.iter().rev().skip(1) {
+        assert!(stack.undo(&mut map).unwrap());
+        assert_eq!(&snapshot(&map), expected);
+    }
+    assert!(!stack.undo(&mut map).unwrap());
+    assert_eq!(map.edge_data(a, first_connection), door);
+
+    for expected in snapshots.iter().skip(1) {
+        assert!(stack.redo(&mut map).unwrap());
+        assert_eq!(&snapshot(&map), expected);
+    }
+    assert!(!stack.redo(&mut map).unwrap());
+    // Redoing the removal of `a` doesn't bring back a stale room id.
+    assert!(!map.rooms.contains_key(&a));
+}
+
+#[test]
+fn transactions_are_undone_at_once() {
+    let mut map = common::generated_map(2, 6);
+    let before = snapshot(&map);
+    let origin = map.sorted_ids()[0];
+    let mut stack = UndoStack::new();
+
+    stack.begin();
+    let room = stack
+        .apply(
+            &mut map,
+            Edit::AddRoom {
+                data: 1,
+                position: (0f32, 300f32),
+            },
+        )
+        .unwrap();
+    stack.begin();
+    stack.apply(&mut map, Edit::Connect(room, origin)).unwrap();
+    stack.apply(&mut map, Edit::Connect(origin, room)).unwrap();
+    stack.commit();
+    assert!(!stack.can_undo());
+    stack.commit();
+    let after = snapshot(&map);
+
+    assert!(stack.undo(&mut map).unwrap());
+    assert_eq!(snapshot(&map), before);
+    assert!(!stack.can_undo());
+    assert!(stack.redo(&mut map).unwrap());
+    assert_eq!(snapshot(&map), after);
+
+    // A new edit drops what could be redone.
+    stack.undo(&mut map).unwrap();
+    stack.apply(&mut map, Edit::SetData(origin, 9)).unwrap();
+    assert!(!stack.can_redo());
+
+    stack.begin();
+    stack.apply(&mut map, Edit::RemoveRoom(origin)).unwrap();
+    stack.rollback(&mut map).unwrap();
+    assert!(map.rooms.contains_key(&origin));
+    assert_eq!(map.rooms[&origin].data, 9);
+}
+
+#[test]
+fn invalid_edits_are_rejected() {
+    let mut map = common::generated_map(3, 4);
+    let mut stack = UndoStack::new();
+    let missing = map.create_raw(0, (900f32, 900f32), vec![]);
+    map.remove(missing);
+
+    assert!(matches!(
+        stack.apply(&mut map, Edit::RemoveRoom(missing)),
+        Err(EditError::InexistantRoomId(_))
+    ));
+    assert!(matches!(
+        stack.apply(&mut map, Edit::MoveRoom(missing, (0f32, 0f32))),
+        Err(EditError::InexistantRoomId(_))
+    ));
+    let first = map.sorted_ids()[0];
+    assert!(stack
+        .apply(&mut map, Edit::Connect(first, missing))
+        .is_err());
+    assert!(!stack.can_undo());
+}
+
+#[test]
+fn failed_undo_leaves_map_and_stack_unchanged() {
+    let mut map = common::generated_map(4, 6);
+    let ids = map.sorted_ids();
+    let (moved, renamed) = (ids[1], ids[2]);
+    let mut stack = UndoStack::new();
+    stack.begin();
+    stack
+        .apply(&mut map, Edit::MoveRoom(moved, (700f32, 0f32)))
+        .unwrap();
+    stack.apply(&mut map, Edit::SetData(renamed, 4)).unwrap();
+    stack.commit();
+
+    // Removed behind the stack's back, the move can't be undone anymore.
+    let room = map.remove(moved).unwrap();
+    let before = snapshot(&map);
+    assert!(matches!(
+        stack.undo(&mut map),
+        Err(EditError::InexistantRoomId(id)) if id == moved
+    ));
+    assert_eq!(snapshot(&map), before);
+    assert_eq!(map.rooms[&renamed].data, 4);
+    assert!(stack.can_undo());
+    assert!(!stack.can_redo());
+
+    // Once put back, the whole transaction is undone.
+    map.restore(moved, room).unwrap();
+    assert!(stack.undo(&mut map).unwrap());
+    assert_eq!(map.rooms[&renamed].data, 0);
+    assert_ne!(map.rooms[&moved].position, (700f32, 0f32));
+
+    // A failed rollback keeps the transaction open.
+    stack.begin();
+    stack.apply(&mut map, Edit::SetData(renamed, 8)).unwrap();
+    stack.apply(&mut map, Edit::RemoveRoom(moved)).unwrap();
+    let room = map.remove(renamed).unwrap();
+    let before = snapshot(&map);
+    assert!(stack.rollback(&mut map).is_err());
+    assert_eq!(snapshot(&map), before);
+    map.restore(renamed, room).unwrap();
+    stack.rollback(&mut map).unwrap();
+    assert!(map.rooms.contains_key(&moved));
+    assert_eq!(map.rooms[&renamed].data, 0);
+}
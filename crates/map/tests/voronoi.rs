@@ -0,0 +1,73 @@
+mod common;
+
+use map::{voronoi::Voronoi, Map};
+
+fn total_area(voronoi: &Voronoi) -> f32 {
+    voronoi
+        .cells()
+        .map(|(id, _)| voronoi.area(id).unwrap())
+        .sum()
+}
+
+fn assert_same_cells(a: &Voronoi, b: &Voronoi) {
+    let (a_ids, b_ids): (Vec<_>, Vec<_>) = (
+        a.cells().map(|c| c.0).collect(),
+        b.cells().map(|c| c.0).collect(),
+    );
+    assert_eq!(a_ids, b_ids);
+    for id in a_ids {
+        assert!(
+            (a.area(id).unwrap() - b.area(id).unwrap()).abs() < 0.1f32,
+            "room {}",
+            id
+        );
+    }
+}
+
+#[test]
+fn two_rooms_split_the_bounds() {
+    let mut map = Map::default();
+    let left = map.create_raw(0, (0f32, 0f32), vec![]);
+    let right = map.create_raw(0, (100f32, 0f32), vec![]);
+    let voronoi = Voronoi::with_bounds(&map, (-50f32, -50f32), (150f32, 50f32));
+
+    assert_eq!(voronoi.area(left), Some(10000f32));
+    assert_eq!(voronoi.area(right), Some(10000f32));
+    assert_eq!(voronoi.room_at((49f32, 40f32)), Some(left));
+    assert_eq!(voronoi.room_at((51f32, -40f32)), Some(right));
+    assert_eq!(voronoi.room_at((51f32, 60f32)), None);
+    assert!(voronoi.cell(left).unwrap().iter().all(|(x, _)| *x <= 50f32));
+}
+
+#[test]
+fn cells_tile_the_bounds() {
+    let map = common::generated_map(6, 30);
+    let voronoi = Voronoi::new(&map, 50f32);
+    let bounds_area = (voronoi.max.0 - voronoi.min.0) * (voronoi.max.1 - voronoi.min.1);
+    assert!((total_area(&voronoi) - bounds_area).abs() / bounds_area < 1e-3);
+
+    for (id, room) in map.rooms.iter() {
+        assert_eq!(voronoi.room_at(room.position), Some(*id));
+        assert!(voronoi.area(*id).unwrap() > 0f32);
+    }
+}
+
+#[test]
+fn incremental_updates_match_a_full_computation() {
+    let mut map = common::generated_map(8, 30);
+    let mut voronoi = Voronoi::new(&map, 50f32);
+    let (min, max) = (voronoi.min, voronoi.max);
+
+    let ids = map.sorted_ids();
+    for id in ids.iter().step_by(3) {
+        map.remove(*id);
+    }
+    let moved = ids[1];
+    map.rooms.get_mut(&moved).unwrap().position.0 += 7f32;
+    map.create_raw(0, (min.0 + 10f32, min.1 + 10f32), vec![]);
+    voronoi.update(&map);
+
+    assert_same_cells(&voronoi, &Voronoi::with_bounds(&map, min, max));
+    let bounds_area = (max.0 - min.0) * (max.1 - min.1);
+    assert!((total_area(&voronoi) - bounds_area).abs() / bounds_area < 1e-3);
+}
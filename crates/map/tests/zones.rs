@@ -0,0 +1,161 @@
+mod common;
+
+use map::{pathfinding, zones::Zones, Map, RoomId};
+
+/// Two fully connected groups of `size` rooms, 1000 units apart, linked by a single corridor.
+fn two_groups(size: usize) -> (Map<i32>, Vec<RoomId>, Vec<RoomId>) {
+    let mut map = Map::default();
+    let mut groups = vec![];
+    for center in [0f32, 1000f32] {
+        let ids: Vec<RoomId> = (0..size)
+            .map(|i| {
+                let angle = i as f32 / size as f32 * std::f32::consts::TAU;
+                map.create_raw(
+                    0,
+                    (center + angle.cos() * 100f32, angle.sin() * 100f32),
+                    vec![],
+                )
+            })
+            .collect();
+        for a in ids.iter() {
+            for b in ids.iter() {
+                if a != b {
+                    map.connect(*a, *b).unwrap();
+                }
+            }
+        }
+        groups.push(ids);
+    }
+    let (left, right) = (groups[0].clone(), groups[1].clone());
+    map.connect(left[0], right[size / 2]).unwrap();
+    map.connect(right[size / 2], left[0]).unwrap();
+    (map, left, right)
+}
+
+#[test]
+fn k_means_splits_distant_groups() {
+    let (map, left, right) = two_groups(6);
+    let zones = Zones::k_means(&map, 2, &mut common::seeded_rng(0), 20);
+    assert_eq!(zones.zones.len(), 2);
+    let left_zone = zones.zone_of(left[0]).unwrap();
+    let right_zone = zones.zone_of(right[0]).unwrap();
+    assert_ne!(left_zone, right_zone);
+    assert_eq!(zones.rooms_in(left_zone), left);
+    assert_eq!(zones.rooms_in(right_zone), right);
+    assert!(zones.are_adjacent(left_zone, right_zone));
+    assert_eq!(zones.adjacent_zones(left_zone), vec![right_zone]);
+}
+
+#[test]
+fn k_means_handles_degenerate_parameters() {
+    let (map, left, right) = two_groups(4);
+    let zones = Zones::k_means(&map, 0, &mut common::seeded_rng(0), 20);
+    assert!(zones.zones.is_empty());
+    assert!(map
+        .sorted_ids()
+        .iter()
+        .all(|id| zones.zone_of(*id).is_none()));
+
+    // Without iterations, rooms still go to their closest starting center.
+    let zones = Zones::k_means(&map, 2, &mut common::seeded_rng(0), 0);
+    assert!(map
+        .sorted_ids()
+        .iter()
+        .all(|id| zones.zone_of(*id).is_some()));
+    assert!(zones.zones.len() <= 2);
+    let single = Zones::k_means(&map, 1, &mut common::seeded_rng(0), 0);
+    assert_eq!(single.zones.len(), 1);
+    assert_eq!(
+        single.rooms_in(single.zone_of(left[0]).unwrap()).len(),
+        left.len() + right.len()
+    );
+}
+
+#[test]
+fn label_propagation_keeps_communities_together() {
+    let (map, left, right) = two_groups(6);
+    for seed in 0..50 {
+        let zones = Zones::label_propagation(&map, &mut common::seeded_rng(seed), 100);
+        assert!(map
+            .sorted_ids()
+            .iter()
+            .all(|id| zones.zone_of(*id).is_some()));
+        for group in [&left, &right] {
+            let zone = zones.zone_of(group[1]);
+            assert!(group[1..].iter().all(|id| zones.zone_of(*id) == zone));
+        }
+    }
+}
+
+#[test]
+fn hierarchical_path_is_valid() {
+    for seed in 0..50 {
+        let map = common::generated_map(seed, 40);
+        let zones = Zones::k_means(&map, 4, &mut common::seeded_rng(seed), 20);
+        let ids = map.sorted_ids();
+        let (from, to) = (ids[0], *ids.last().unwrap());
+        let path = zones.find_path(&map, from, to);
+        let direct = pathfinding::shortest_path(&map, from, to);
+        assert_eq!(path.is_some(), direct.is_some());
+        if let (Some(path), Some(direct)) = (path, direct) {
+            assert_eq!(path.first(), Some(&from));
+            assert_eq!(path.last(), Some(&to));
+            assert!(path
+                .windows(2)
+                .all(|w| map.rooms[&w[0]].connections.contains(&w[1])));
+            assert!(path_length(&map, &path) >= path_length(&map, &direct) - 0.01);
+        }
+    }
+}
+
+#[test]
+fn adjacency_follows_assignments_and_map_changes() {
+    let (mut map, left, right) = two_groups(4);
+    map.record_events(true);
+    let mut zones = Zones::default();
+    let (a, b) = (zones.add_default_zone(), zones.add_default_zone());
+    for room in left.iter() {
+        zones.assign(*room, a);
+    }
+    zones.sync(&map);
+    assert!(zones.adjacent_zones(a).is_empty());
+    for room in right.iter() {
+        zones.assign(*room, b);
+    }
+    assert_eq!(zones.adjacent_zones(a), vec![b]);
+    assert_eq!(zones.zone_path(a, b), Some(vec![a, b]));
+
+    // One direction of the only corridor left still links the zones.
+    map.disconnect(left[0], right[2]);
+    zones.apply_events(map.events());
+    map.drain_events().for_each(drop);
+    assert!(zones.are_adjacent(a, b));
+    map.disconnect(right[2], left[0]);
+    zones.apply_events(map.events());
+    map.drain_events().for_each(drop);
+    assert!(!zones.are_adjacent(b, a));
+    assert_eq!(zones.zone_path(a, b), None);
+
+    map.connect(left[1], right[1]).unwrap();
+    zones.apply_events(map.events());
+    map.drain_events().for_each(drop);
+    assert!(zones.are_adjacent(a, b));
+
+    // The zone of a room is part of the crossings of its connections.
+    zones.unassign(left[1]);
+    assert!(!zones.are_adjacent(a, b));
+    zones.assign(left[1], a);
+    assert!(zones.are_adjacent(a, b));
+    map.remove(right[1]);
+    zones.apply_events(map.events());
+    assert_eq!(zones.zone_of(right[1]), None);
+    assert!(zones.adjacent_zones(a).is_empty());
+}
+
+fn path_length(map: &Map<i32>, path: &[RoomId]) -> f32 {
+    path.windows(2)
+        .map(|w| {
+            poisson::distance_squared(&map.rooms[&w[0]].position, &map.rooms[&w[1]].position).sqrt()
+        })
+        .sum()
+}
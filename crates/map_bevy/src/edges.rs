@@ -0,0 +1,343 @@
+//! Appearance of connections: direction, locks and cost, from the [`ConnectionEntity`] data.
+
+use std::f32::consts::PI;
+
+use bevy::prelude::*;
+use bevy_prototype_lyon::{
+    entity::Path,
+    prelude::{DrawMode, PathBuilder, StrokeMode},
+};
+
+use crate::ConnectionEntity;
+
+/// Points a bent corridor is resampled to.
+const BEND_SAMPLES: usize = 16;
+
+/// How connections are drawn, see [`EdgeStyleOverride`] to change a single one.
+#[derive(Debug, Clone)]
+pub struct EdgeStyle {
+    pub color: Color,
+    /// Width of a connection with a terrain of 1.
+    pub width: f32,
+    /// Multiplies the width by the terrain of the connection, so costly ones look heavier.
+    pub width_by_terrain: bool,
+    /// Length of the arrowheads of one-way connections, 0 to hide them.
+    pub arrow_length: f32,
+    /// Arrowheads stop this far from the center of the room they point to.
+    pub room_clearance: f32,
+    /// Dash and gap lengths of locked connections.
+    pub locked_dash: (f32, f32),
+    /// Distance between the middles of the two curves of a two-way connection, so both ways
+    /// and their arrowheads can be told apart. 0 draws both ways as a single line.
+    pub two_way_bend: f32,
+}
+
+impl Default for EdgeStyle {
+    fn default() -> Self {
+        Self {
+            color: Color::ORANGE_RED,
+            width: 10.0,
+            width_by_terrain: false,
+            arrow_length: 20.0,
+            room_clearance: 15.0,
+            locked_dash: (20.0, 12.0),
+            two_way_bend: 16.0,
+        }
+    }
+}
+
+/// Overrides the [`EdgeStyle`] of a connection entity, `None` keeps the style.
+#[derive(Component, Debug, Clone, Default)]
+pub struct EdgeStyleOverride {
+    pub color: Option<Color>,
+    /// Replaces the width, terrain included, for instance to show traffic.
+    pub width: Option<f32>,
+    pub dashed: Option<bool>,
+    pub two_way_bend: Option<f32>,
+}
+
+impl EdgeStyle {
+    /// Style of a connection with its override applied.
+    pub fn with_override(&self, style_override: Option<&EdgeStyleOverride>) -> Self {
+        let mut style = self.clone();
+        if let Some(style_override) = style_override {
+            style.color = style_override.color.unwrap_or(style.color);
+            style.two_way_bend = style_override.two_way_bend.unwrap_or(style.two_way_bend);
+        }
+        style
+    }
+
+    /// Width of the connection, its costliest way giving the terrain.
+    ///
+    /// Non-finite terrains are ignored, so an impassable way doesn't make the width infinite.
+    pub fn width_of(
+        &self,
+        connection: &ConnectionEntity,
+        style_override: Option<&EdgeStyleOverride>,
+    ) -> f32 {
+        if let Some(width) = style_override.and_then(|o| o.width) {
+            return width;
+        }
+        if !self.width_by_terrain {
+            return self.width;
+        }
+        let terrain = [connection.forward, connection.backward]
+            .iter()
+            .flatten()
+            .map(|data| data.terrain)
+            .filter(|terrain| terrain.is_finite())
+            .reduce(f32::max)
+            .unwrap_or(1f32);
+        self.width * terrain
+    }
+
+    pub fn draw_mode(
+        &self,
+        connection: &ConnectionEntity,
+        style_override: Option<&EdgeStyleOverride>,
+    ) -> DrawMode {
+        DrawMode::Stroke(StrokeMode::new(
+            self.with_override(style_override).color,
+            self.width_of(connection, style_override),
+        ))
+    }
+
+    /// Lines of both directions of the connection, dashed and with arrowheads as needed.
+    pub fn path(
+        &self,
+        connection: &ConnectionEntity,
+        style_override: Option<&EdgeStyleOverride>,
+    ) -> Path {
+        let style = self.with_override(style_override);
+        let dash = style_override
+            .and_then(|o| o.dashed)
+            .unwrap_or(connection.locked)
+            .then_some(style.locked_dash);
+        let mut builder = PathBuilder::new();
+        let two_way = connection.forward.is_some() && connection.backward.is_some();
+        if two_way && style.two_way_bend > 0f32 {
+            // Each way bends to its right, so both curves part.
+            let backward: Vec<(f32, f32)> = connection.points.iter().rev().copied().collect();
+            for points in [connection.points.clone(), backward] {
+                let curve = bend(&points, style.two_way_bend / 2f32);
+                add_line(&mut builder, &curve, dash);
+                add_arrow(
+                    &mut builder,
+                    &curve,
+                    style.arrow_length,
+                    style.room_clearance,
+                );
+            }
+        } else {
+            add_line(&mut builder, &connection.points, dash);
+            if !two_way {
+                let mut points = connection.points.clone();
+                if connection.forward.is_none() {
+                    points.reverse();
+                }
+                add_arrow(
+                    &mut builder,
+                    &points,
+                    style.arrow_length,
+                    style.room_clearance,
+                );
+            }
+        }
+        builder.build()
+    }
+}
+
+fn distance(a: (f32, f32), b: (f32, f32)) -> f32 {
+    ((b.0 - a.0).powi(2) + (b.1 - a.1).powi(2)).sqrt()
+}
+
+fn length(points: &[(f32, f32)]) -> f32 {
+    points.windows(2).map(|w| distance(w[0], w[1])).sum()
+}
+
+/// Point at `along` from the start of the line, and the direction of the line there.
+fn point_at(points: &[(f32, f32)], along: f32) -> ((f32, f32), (f32, f32)) {
+    let mut left = along.max(0f32);
+    for w in points.windows(2) {
+        let segment = distance(w[0], w[1]);
+        if segment == 0f32 {
+            continue;
+        }
+        let direction = ((w[1].0 - w[0].0) / segment, (w[1].1 - w[0].1) / segment);
+        if left <= segment {
+            return (
+                (w[0].0 + direction.0 * left, w[0].1 + direction.1 * left),
+                direction,
+            );
+        }
+        left -= segment;
+    }
+    let last = *points.last().unwrap();
+    let before = points[points.len().saturating_sub(2)];
+    let segment = distance(before, last).max(1e-6);
+    (
+        last,
+        ((last.0 - before.0) / segment, (last.1 - before.1) / segment),
+    )
+}
+
+/// Resamples the line and pushes it to its right, most in its middle.
+fn bend(points: &[(f32, f32)], offset: f32) -> Vec<(f32, f32)> {
+    let total = length(points);
+    (0..=BEND_SAMPLES)
+        .map(|i| {
+            let t = i as f32 / BEND_SAMPLES as f32;
+            let ((x, y), (dx, dy)) = point_at(points, total * t);
+            let push = offset * (PI * t).sin();
+            (x + dy * push, y - dx * push)
+        })
+        .collect()
+}
+
+fn add_line(builder: &mut PathBuilder, points: &[(f32, f32)], dash: Option<(f32, f32)>) {
+    for line in dashes(points, dash) {
+        builder.move_to(line[0].into());
+        for point in line.iter().skip(1) {
+            builder.line_to((*point).into());
+        }
+    }
+}
+
+/// Pieces of the line left by `dash`, following its corners, or the whole line when not dashed.
+fn dashes(points: &[(f32, f32)], dash: Option<(f32, f32)>) -> Vec<Vec<(f32, f32)>> {
+    let (dash, gap) = match dash {
+        Some((dash, gap)) if dash > 0f32 => (dash, gap.max(0f32)),
+        _ => return vec![points.to_vec()],
+    };
+    let total = length(points);
+    let mut lines = vec![];
+    let mut start = 0f32;
+    while start < total {
+        let end = (start + dash).min(total);
+        let mut line = vec![point_at(points, start).0];
+        let mut walked = 0f32;
+        for w in points.windows(2) {
+            walked += distance(w[0], w[1]);
+            if walked > start && walked < end {
+                line.push(w[1]);
+            }
+        }
+        line.push(point_at(points, end).0);
+        lines.push(line);
+        start = end + gap;
+    }
+    lines
+}
+
+fn add_arrow(builder: &mut PathBuilder, points: &[(f32, f32)], length: f32, clearance: f32) {
+    if let Some([left, tip, right]) = arrow(points, length, clearance) {
+        builder.move_to(left.into());
+        builder.line_to(tip.into());
+        builder.line_to(right.into());
+    }
+}
+
+/// Left end, tip and right end of an open arrowhead pointing to the end of the line.
+///
+/// `None` when hidden, or when the line is too short to leave `clearance` before its end.
+fn arrow(points: &[(f32, f32)], length: f32, clearance: f32) -> Option<[(f32, f32); 3]> {
+    let total = self::length(points);
+    if length <= 0f32 || total <= clearance {
+        return None;
+    }
+    let ((x, y), (dx, dy)) = point_at(points, total - clearance);
+    let back = (x - dx * length, y - dy * length);
+    let half_width = length / 2f32;
+    Some([
+        (back.0 + dy * half_width, back.1 - dx * half_width),
+        (x, y),
+        (back.0 - dy * half_width, back.1 + dx * half_width),
+    ])
+}
+
+#[cfg(test)]
+mod tests {
+    use map::{EdgeData, RoomId};
+
+    use super::*;
+
+    fn assert_close(actual: (f32, f32), expected: (f32, f32)) {
+        assert!(
+            distance(actual, expected) < 1e-3,
+            "{:?} is not {:?}",
+            actual,
+            expected
+        );
+    }
+
+    #[test]
+    fn bend_pushes_the_middle_to_the_right() {
+        let curve = bend(&[(0f32, 0f32), (100f32, 0f32)], 10f32);
+        assert_eq!(curve.len(), BEND_SAMPLES + 1);
+        assert_close(curve[0], (0f32, 0f32));
+        assert_close(curve[BEND_SAMPLES], (100f32, 0f32));
+        // Going along +x, the right is -y.
+        assert_close(curve[BEND_SAMPLES / 2], (50f32, -10f32));
+        assert!(curve.iter().all(|point| point.1 <= 1e-3));
+    }
+
+    #[test]
+    fn dashes_follow_corners() {
+        let corner = [(0f32, 0f32), (10f32, 0f32), (10f32, 10f32)];
+        let lines = dashes(&corner, Some((8f32, 4f32)));
+        assert_eq!(lines.len(), 2);
+        assert_close(lines[0][0], (0f32, 0f32));
+        assert_close(*lines[0].last().unwrap(), (8f32, 0f32));
+        // The second dash starts after the corner, the next ones go round it.
+        assert_eq!(lines[1].len(), 2);
+        assert_close(lines[1][0], (10f32, 2f32));
+        assert_close(lines[1][1], (10f32, 10f32));
+
+        let lines = dashes(&corner, Some((6f32, 2f32)));
+        assert_eq!(lines.len(), 3);
+        assert_eq!(lines[1], vec![(8f32, 0f32), (10f32, 0f32), (10f32, 4f32)]);
+
+        assert_eq!(dashes(&corner, None), vec![corner.to_vec()]);
+        assert_eq!(dashes(&corner, Some((0f32, 4f32))), vec![corner.to_vec()]);
+    }
+
+    #[test]
+    fn arrows_need_room_before_the_clearance() {
+        let line = [(0f32, 0f32), (30f32, 0f32)];
+        let [left, tip, right] = arrow(&line, 10f32, 15f32).unwrap();
+        assert_close(tip, (15f32, 0f32));
+        assert_close(left, (5f32, -5f32));
+        assert_close(right, (5f32, 5f32));
+
+        assert_eq!(arrow(&line, 10f32, 30f32), None);
+        assert_eq!(arrow(&[(0f32, 0f32), (10f32, 0f32)], 10f32, 15f32), None);
+        assert_eq!(arrow(&line, 0f32, 15f32), None);
+    }
+
+    #[test]
+    fn width_ignores_non_finite_terrain() {
+        let style = EdgeStyle {
+            width_by_terrain: true,
+            ..Default::default()
+        };
+        let terrain = |terrain| {
+            Some(EdgeData {
+                terrain,
+                door_time: 0f32,
+            })
+        };
+        let mut connection = ConnectionEntity {
+            from: RoomId::default(),
+            to: RoomId::default(),
+            points: vec![(0f32, 0f32), (10f32, 0f32)],
+            forward: terrain(2f32),
+            backward: terrain(f32::INFINITY),
+            locked: false,
+        };
+        assert_eq!(style.width_of(&connection, None), style.width * 2f32);
+        connection.forward = terrain(f32::NAN);
+        assert_eq!(style.width_of(&connection, None), style.width);
+        connection.forward = None;
+        assert_eq!(style.width_of(&connection, None), style.width);
+    }
+}
@@ -1,35 +1,116 @@
-use bevy::{ecs::component::TableStorage, prelude::*, sprite::MaterialMesh2dBundle};
-use bevy_prototype_lyon::{
-    plugin::ShapePlugin,
-    prelude::{DrawMode, FillMode, GeometryBuilder, StrokeMode},
+mod edges;
+mod render;
+
+use std::{collections::BTreeMap, marker::PhantomData};
+
+use bevy::{ecs::component::TableStorage, prelude::*};
+use map::{
+    corridors::{self, Corridor, CorridorConfig},
+    fog::FogOfWar,
+    lock_and_key::LockAndKey,
+    EdgeData, MapEvent, RoomId,
+};
+
+pub use connections::DisplayConnections;
+pub use edges::{EdgeStyle, EdgeStyleOverride};
+pub use render::{
+    FogViewer, MapRenderPlugin, MapStyle, MapStylePlugin, RoomShape, RoomStyle, RoomTextStyle,
 };
-use map::{Room, RoomId};
-use shapes::*;
-pub struct MapPlugin;
 
-impl Plugin for MapPlugin {
+/// Keeps every [`Map<T>`] in sync with its entities, without drawing anything: works with
+/// `MinimalPlugins`, see [`MapRenderPlugin`] to display them.
+pub struct MapCorePlugin<T>(PhantomData<T>);
+
+impl<T> Default for MapCorePlugin<T> {
+    fn default() -> Self {
+        Self(PhantomData)
+    }
+}
+
+#[derive(SystemLabel, Debug, Clone, PartialEq, Eq, Hash)]
+pub enum MapSystem {
+    Events,
+    Corridors,
+    /// Spawns, despawns and moves room and connection entities.
+    Entities,
+}
+
+impl<T: Send + Sync + 'static> Plugin for MapCorePlugin<T> {
     fn build(&self, app: &mut App) {
-        app.add_plugins(DefaultPlugins);
-        app.add_plugin(ShapesPlugin);
-
-        app.add_plugin(ShapePlugin);
-        /*
-        #[cfg(target_arch = "wasm32")]
-        app.add_plugin(bevy_webgl2::WebGL2Plugin);
-
-        app.add_plugin(EguiPlugin)
-            .add_plugin(MapGraphPlugin)
-            .add_state(AppState::Menu)
-            .add_system(ui_menu.system())
-            .add_system(game_menu.system());*/
-
-        app.add_system_to_stage(CoreStage::PreUpdate, update_map_display);
-        app.add_system_to_stage(CoreStage::PreUpdate, connections::update_map_connections);
+        app.add_event::<MapChanged>();
+        app.add_system_to_stage(
+            CoreStage::PreUpdate,
+            forward_map_events::<T>
+                .label(MapSystem::Events)
+                .before(MapSystem::Corridors),
+        );
+        app.init_resource::<CorridorConfig>();
+        app.add_system_to_stage(
+            CoreStage::PreUpdate,
+            update_corridors::<T>.label(MapSystem::Corridors),
+        );
+        app.add_system_to_stage(
+            CoreStage::PreUpdate,
+            update_room_entities::<T>
+                .label(MapSystem::Entities)
+                .after(MapSystem::Corridors),
+        );
+        app.add_system_to_stage(
+            CoreStage::PreUpdate,
+            connections::update_map_connections::<T>
+                .label(MapSystem::Entities)
+                .after(MapSystem::Corridors),
+        );
+    }
+}
+
+pub struct Map<T>(pub map::Map<T>);
+
+impl<T: Send + Sync + 'static> Component for Map<T> {
+    type Storage = TableStorage;
+}
+
+impl<T> Default for Map<T> {
+    fn default() -> Self {
+        Self(map::Map::default())
     }
 }
 
+/// A change of a [`Map`], forwarded from its [`map::Map::drain_events`] once per frame.
+///
+/// Recording is turned on for every map, changes made before its first frame are not sent.
+pub struct MapChanged {
+    pub map: Entity,
+    pub event: MapEvent,
+}
+
+/// Corridor of every connection, routed around rooms whenever the map changes.
 #[derive(Component, Default)]
-pub struct Map(pub map::Map<i32>);
+pub struct Corridors(pub BTreeMap<(RoomId, RoomId), Corridor>);
+
+impl Corridors {
+    /// Point at `t` of the corridor going from `from` to `to`, whichever way it was routed.
+    pub fn point_at(&self, from: RoomId, to: RoomId, t: f32) -> Option<(f32, f32)> {
+        if from < to {
+            self.0.get(&(from, to)).map(|c| c.point_at(t))
+        } else {
+            self.0.get(&(to, from)).map(|c| c.point_at(1f32 - t))
+        }
+    }
+
+    /// Length of the corridor between two rooms, the same both ways.
+    pub fn length(&self, a: RoomId, b: RoomId) -> Option<f32> {
+        self.0.get(&(a.min(b), a.max(b))).map(Corridor::length)
+    }
+}
+
+/// What each faction knows of the map, updated by the game.
+#[derive(Component, Default)]
+pub struct Fog(pub FogOfWar);
+
+/// Locked connections of the map, drawn dashed.
+#[derive(Component, Default)]
+pub struct Locks(pub LockAndKey);
 
 #[derive(Component, Default)]
 pub struct DisplayMap {
@@ -41,83 +122,98 @@ pub struct DisplayMap {
 pub struct RoomEntity {
     pub room_id: map::RoomId,
 }
-pub struct RoomGraphUpdate {
-    pub mesh_bundle: MaterialMesh2dBundle<shapes::ColorMaterial>,
+/// Line of a connection, shared by both directions of two-way connections.
+#[derive(Component, Debug, PartialEq)]
+pub struct ConnectionEntity {
+    /// Lowest room id of the connection.
+    pub from: RoomId,
+    pub to: RoomId,
+    /// Corridor from `from` to `to`.
+    pub points: Vec<(f32, f32)>,
+    /// Data of the connection from `from` to `to`, `None` if it only goes the other way.
+    pub forward: Option<EdgeData>,
+    pub backward: Option<EdgeData>,
+    /// Locked by the [`Locks`] of the map.
+    pub locked: bool,
 }
-fn create_room_bundle(shapes: &Res<ShapeMeshes>, pos: (f32, f32)) -> RoomGraphUpdate {
-    let mut transform = Transform::from_xyz(pos.0, pos.1, 10.0);
-    transform.scale = Vec3::ONE * 15.0;
-    let mesh = MaterialMesh2dBundle {
-        mesh: shapes.quad2x2.clone().into(),
-        material: shapes.mat_green.clone(),
-        transform,
-        ..Default::default()
-    };
-    RoomGraphUpdate { mesh_bundle: mesh }
+
+fn forward_map_events<T: Send + Sync + 'static>(
+    mut maps: Query<(Entity, &mut Map<T>)>,
+    mut changes: EventWriter<MapChanged>,
+) {
+    for (entity, mut map) in maps.iter_mut() {
+        // Maps only record once asked to, right when they are added.
+        if !map.0.is_recording_events() {
+            map.0.record_events(true);
+            continue;
+        }
+        // Only borrowed mutably when there is something to drain, to keep `Changed<Map>` exact.
+        if map.0.events().is_empty() {
+            continue;
+        }
+        for event in map.0.drain_events() {
+            changes.send(MapChanged { map: entity, event });
+        }
+    }
+}
+
+fn update_corridors<T: Send + Sync + 'static>(
+    config: Res<CorridorConfig>,
+    mut maps: Query<(&Map<T>, &mut Corridors), Changed<Map<T>>>,
+) {
+    for (map, mut corridors) in maps.iter_mut() {
+        corridors.0 = corridors::route_all(&map.0, &config);
+    }
 }
 
-fn update_map_display(
+/// Spawns an entity for every new room, despawns the ones of removed rooms and moves the
+/// others where their room is.
+fn update_room_entities<T: Send + Sync + 'static>(
     mut commands: Commands,
-    shapes: Res<ShapeMeshes>,
-    displays: Query<Entity, With<RoomEntity>>,
-    mut maps: Query<(&mut Map, &mut DisplayMap), Changed<Map>>,
+    mut displays: Query<&mut Transform, With<RoomEntity>>,
+    mut maps: Query<(&Map<T>, &mut DisplayMap), Changed<Map<T>>>,
 ) {
-    for (mut map, mut display) in maps.iter_mut() {
-        let mut to_remove = vec![];
-        for (id, room) in map.0.iter() {
-            if let Some(entity) = display.get_entity(*id) {
-                match displays.get(entity) {
-                    Ok(e) => {
-                        // TODO: check if needs to update ?
-                    }
-                    Err(_) => {
-                        to_remove.push(*id);
+    for (map, mut display) in maps.iter_mut() {
+        let removed: Vec<RoomId> = display
+            .ids
+            .iter()
+            .filter(|id| !map.0.rooms.contains_key(id))
+            .copied()
+            .collect();
+        for id in removed {
+            if let Some(entity) = display.get_entity(id) {
+                // With the texts drawn over it.
+                commands.entity(entity).despawn_recursive();
+            }
+            display.remove(id);
+        }
+
+        for id in map.0.sorted_ids() {
+            let room = &map.0.rooms[&id];
+            if let Some(entity) = display.get_entity(id) {
+                match displays.get_mut(entity) {
+                    Ok(mut transform) => {
+                        let position = Vec2::new(room.position.0, room.position.1);
+                        if transform.translation.truncate() != position {
+                            transform.translation = position.extend(transform.translation.z);
+                        }
                         continue;
                     }
-                }
-            } else {
-                // Create new room entity
-                let ent = commands.spawn().insert(RoomEntity { room_id: *id }).id();
-                display.add(*id, ent);
-                let graphic_update = create_room_bundle(&shapes, room.position);
-                commands
-                    .entity(ent)
-                    .insert_bundle(graphic_update.mesh_bundle);
-
-                // Create new connection entities
-                for c in room.connections.iter() {
-                    // TODO: only add visible connections if id is from > to (to avoid duplicate connections)
-                    // TODO: later, handle one way connections
-
-                    let target = map.0.rooms[c].position;
-                    let connection_def = (c, id);
-
-                    let start = Vec2::new(room.position.0, room.position.1);
-                    let end = Vec2::new(target.0, target.1);
-
-                    let line = bevy_prototype_lyon::shapes::Line(start, end);
-
-                    let mut builder = GeometryBuilder::new().add(&line);
-
-                    commands.spawn_bundle(builder.build(
-                        DrawMode::Outlined {
-                            fill_mode: FillMode::color(Color::ORANGE_RED),
-                            outline_mode: StrokeMode::new(Color::ORANGE_RED, 10.0),
-                        },
-                        Transform::default(),
-                    ));
-                    // TODO: add connections handle to remove them later
-                    //connections.add(connection_def, connection_entity);
+                    // Despawned by someone else, spawned again.
+                    Err(_) => display.remove(id),
                 }
             }
-        }
-
-        for r in to_remove {
-            display.remove(r);
-            // TODO: remove all connections containing that
+            let ent = commands
+                .spawn()
+                .insert(RoomEntity { room_id: id })
+                .insert(Transform::from_xyz(room.position.0, room.position.1, 10.0))
+                .insert(GlobalTransform::default())
+                .id();
+            display.add(id, ent);
         }
     }
 }
+
 impl DisplayMap {
     pub fn get_entity(&self, id: RoomId) -> Option<Entity> {
         for i in 0..self.ids.len() {
@@ -144,6 +240,9 @@ mod connections {
     use bevy::prelude::*;
     use map::RoomId;
 
+    use super::{ConnectionEntity, Corridors, Locks, Map};
+
+    /// Entity of every displayed connection, by `(lowest, highest)` room ids.
     #[derive(Default, Component)]
     pub struct DisplayConnections {
         pub entities: Vec<Entity>,
@@ -151,10 +250,8 @@ mod connections {
     }
 
     impl DisplayConnections {
-        pub fn get_entity(&self, mut id: (RoomId, RoomId)) -> Option<Entity> {
-            if id.0 < id.1 {
-                id = (id.1, id.0);
-            }
+        pub fn get_entity(&self, id: (RoomId, RoomId)) -> Option<Entity> {
+            let id = ordered(id);
             for i in 0..self.ids.len() {
                 if self.ids[i] == id {
                     return Some(self.entities[i]);
@@ -162,7 +259,7 @@ mod connections {
             }
             None
         }
-        pub fn get_entities(&self, mut id: RoomId) -> Vec<Entity> {
+        pub fn get_entities(&self, id: RoomId) -> Vec<Entity> {
             let mut res = vec![];
             for i in 0..self.ids.len() {
                 if self.ids[i].0 == id || self.ids[i].1 == id {
@@ -173,19 +270,94 @@ mod connections {
         }
 
         fn add(&mut self, id: (RoomId, RoomId), ent: Entity) {
-            self.ids.push(id);
+            self.ids.push(ordered(id));
             self.entities.push(ent);
         }
-        fn remove(&mut self, mut ids: (RoomId, RoomId)) {
-            if ids.0 < ids.1 {
-                ids = (ids.1, ids.0);
+        fn remove(&mut self, ids: (RoomId, RoomId)) -> Option<Entity> {
+            let ids = ordered(ids);
+            let index = self.ids.iter().position(|id| *id == ids)?;
+            self.ids.remove(index);
+            Some(self.entities.remove(index))
+        }
+    }
+
+    fn ordered(id: (RoomId, RoomId)) -> (RoomId, RoomId) {
+        if id.0 < id.1 {
+            id
+        } else {
+            (id.1, id.0)
+        }
+    }
+
+    /// Spawns an entity per connection, despawns the ones of removed connections and follows
+    /// moves.
+    pub fn update_map_connections<T: Send + Sync + 'static>(
+        mut commands: Commands,
+        mut maps: Query<
+            (
+                &Map<T>,
+                &mut DisplayConnections,
+                Option<&Corridors>,
+                Option<&Locks>,
+            ),
+            Or<(Changed<Map<T>>, Changed<Locks>)>,
+        >,
+        mut connections: Query<&mut ConnectionEntity>,
+    ) {
+        for (map, mut display, corridors, locks) in maps.iter_mut() {
+            let edges = map.0.edges();
+            let removed: Vec<(RoomId, RoomId)> = display
+                .ids
+                .iter()
+                .filter(|edge| edges.binary_search(edge).is_err())
+                .copied()
+                .collect();
+            for edge in removed {
+                if let Some(entity) = display.remove(edge) {
+                    commands.entity(entity).despawn();
+                }
             }
-            if let Some(index) = self.ids.iter().position(|id| *id == ids) {
-                self.ids.remove(index);
-                self.entities.remove(index);
+
+            for (from, to) in edges {
+                let points = corridors
+                    .and_then(|corridors| corridors.0.get(&(from, to)))
+                    .map(|corridor| corridor.points.clone())
+                    .unwrap_or_else(|| {
+                        vec![map.0.rooms[&from].position, map.0.rooms[&to].position]
+                    });
+                let direction = |from: RoomId, to: RoomId| {
+                    map.0.rooms[&from]
+                        .connections
+                        .contains(&to)
+                        .then(|| map.0.edge_data(from, to))
+                };
+                let updated = ConnectionEntity {
+                    from,
+                    to,
+                    points,
+                    forward: direction(from, to),
+                    backward: direction(to, from),
+                    locked: locks.is_some_and(|locks| locks.0.lock_of(from, to).is_some()),
+                };
+                match display.get_entity((from, to)) {
+                    Some(entity) => {
+                        if let Ok(mut connection) = connections.get_mut(entity) {
+                            if *connection != updated {
+                                *connection = updated;
+                            }
+                        }
+                    }
+                    None => {
+                        let entity = commands
+                            .spawn()
+                            .insert(updated)
+                            .insert(Transform::default())
+                            .insert(GlobalTransform::default())
+                            .id();
+                        display.add((from, to), entity);
+                    }
+                }
             }
         }
     }
-
-    pub fn update_map_connections() {}
 }
@@ -0,0 +1,411 @@
+//! Drawing of the entities kept by [`MapCorePlugin`](crate::MapCorePlugin).
+
+use std::marker::PhantomData;
+
+use bevy::prelude::*;
+use bevy_prototype_lyon::{
+    entity::{Path, ShapeBundle},
+    plugin::ShapePlugin,
+    prelude::{DrawMode, FillMode, GeometryBuilder},
+    shapes as lyon_shapes,
+};
+use map::{
+    fog::{Faction, Knowledge},
+    RoomId,
+};
+
+use crate::{
+    edges::{EdgeStyle, EdgeStyleOverride},
+    ConnectionEntity, DisplayMap, Fog, Map, RoomEntity,
+};
+
+/// Draws rooms and connections, needs the rendering plugins from `DefaultPlugins`.
+pub struct MapRenderPlugin<T> {
+    pub room_style: fn(&map::Map<T>, RoomId) -> RoomStyle,
+    data: PhantomData<T>,
+}
+
+impl<T> MapRenderPlugin<T> {
+    /// `room_style` is called again for every room of a map whenever the map changes, so
+    /// styles can follow room data, or anything else of the map like hop distances.
+    pub fn new(room_style: fn(&map::Map<T>, RoomId) -> RoomStyle) -> Self {
+        Self {
+            room_style,
+            data: PhantomData,
+        }
+    }
+}
+
+impl<T> Default for MapRenderPlugin<T> {
+    /// Every room is drawn the same.
+    fn default() -> Self {
+        Self::new(|_, _| RoomStyle::default())
+    }
+}
+
+impl<T: Send + Sync + 'static> Plugin for MapRenderPlugin<T> {
+    fn build(&self, app: &mut App) {
+        app.add_plugin(ShapePlugin);
+        app.add_plugin(MapStylePlugin::new(self.room_style));
+        /*
+        #[cfg(target_arch = "wasm32")]
+        app.add_plugin(bevy_webgl2::WebGL2Plugin);
+
+        app.add_plugin(EguiPlugin)
+            .add_plugin(MapGraphPlugin)
+            .add_state(AppState::Menu)
+            .add_system(ui_menu.system())
+            .add_system(game_menu.system());*/
+
+        app.init_resource::<RoomTextStyle>();
+        app.add_system(draw_rooms.after(RenderSystem::StyleRooms));
+        app.init_resource::<EdgeStyle>();
+        app.add_system(draw_connections);
+        app.init_resource::<FogViewer>();
+        // After the drawing components are inserted.
+        app.add_system_to_stage(CoreStage::PostUpdate, apply_fog);
+    }
+}
+
+/// Gives rooms their [`RoomStyle`] without drawing anything, added by [`MapRenderPlugin`].
+pub struct MapStylePlugin<T> {
+    pub room_style: fn(&map::Map<T>, RoomId) -> RoomStyle,
+    data: PhantomData<T>,
+}
+
+impl<T> MapStylePlugin<T> {
+    pub fn new(room_style: fn(&map::Map<T>, RoomId) -> RoomStyle) -> Self {
+        Self {
+            room_style,
+            data: PhantomData,
+        }
+    }
+}
+
+impl<T: Send + Sync + 'static> Plugin for MapStylePlugin<T> {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(MapStyle::<T> {
+            room: self.room_style,
+        });
+        // Entities are spawned in `PreUpdate`.
+        app.add_system(style_rooms::<T>.label(RenderSystem::StyleRooms));
+    }
+}
+
+#[derive(SystemLabel, Debug, Clone, PartialEq, Eq, Hash)]
+enum RenderSystem {
+    StyleRooms,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RoomShape {
+    Square,
+    Circle,
+    Hexagon,
+}
+
+/// How a room is drawn, given by the style function of its [`MapRenderPlugin`].
+#[derive(Component, Debug, Clone, PartialEq)]
+pub struct RoomStyle {
+    pub shape: RoomShape,
+    /// Half the width of the shape.
+    pub size: f32,
+    pub color: Color,
+    /// Short text drawn over the room, like `"$"` for a shop.
+    pub icon: Option<String>,
+    /// Text drawn under the room, like its name or its distance to the start.
+    pub label: Option<String>,
+}
+
+impl Default for RoomStyle {
+    fn default() -> Self {
+        Self {
+            shape: RoomShape::Square,
+            size: 15.0,
+            color: Color::GREEN,
+            icon: None,
+            label: None,
+        }
+    }
+}
+
+/// Style functions of a [`MapStylePlugin`].
+pub struct MapStyle<T> {
+    pub room: fn(&map::Map<T>, RoomId) -> RoomStyle,
+}
+
+/// Text of room icons and labels, every text is written again when it changes.
+///
+/// Nothing is written while `font` is the default handle, set it to a loaded font.
+#[derive(Debug, Clone)]
+pub struct RoomTextStyle {
+    pub font: Handle<Font>,
+    pub font_size: f32,
+    pub color: Color,
+}
+
+impl Default for RoomTextStyle {
+    fn default() -> Self {
+        Self {
+            font: Handle::default(),
+            font_size: 16.0,
+            color: Color::WHITE,
+        }
+    }
+}
+
+/// Text entities of the icon and label of a room, children of the room entity.
+#[derive(Component, Default)]
+struct RoomTexts(Vec<Entity>);
+
+/// Faction whose knowledge is displayed, the whole map is displayed without one.
+#[derive(Default)]
+pub struct FogViewer {
+    pub faction: Option<Faction>,
+}
+
+/// Gives every room of a changed map its style, only replacing the ones which differ.
+fn style_rooms<T: Send + Sync + 'static>(
+    mut commands: Commands,
+    style: Res<MapStyle<T>>,
+    maps: Query<(&Map<T>, &DisplayMap, ChangeTrackers<Map<T>>)>,
+    mut rooms: Query<Option<&mut RoomStyle>, With<RoomEntity>>,
+) {
+    for (map, display, tracker) in maps.iter() {
+        if !tracker.is_changed() && !style.is_changed() {
+            continue;
+        }
+        for (id, entity) in display.ids.iter().zip(display.entities.iter()) {
+            if !map.0.rooms.contains_key(id) {
+                continue;
+            }
+            let room_style = (style.room)(&map.0, *id);
+            match rooms.get_mut(*entity) {
+                Ok(Some(mut current)) => {
+                    if *current != room_style {
+                        *current = room_style;
+                    }
+                }
+                Ok(None) => {
+                    commands.entity(*entity).insert(room_style);
+                }
+                Err(_) => {}
+            }
+        }
+    }
+}
+
+fn room_shape_bundle(style: &RoomStyle, transform: Transform) -> ShapeBundle {
+    let draw_mode = DrawMode::Fill(FillMode::color(style.color));
+    match style.shape {
+        RoomShape::Square => GeometryBuilder::build_as(
+            &lyon_shapes::Rectangle {
+                extents: Vec2::splat(style.size * 2f32),
+                origin: lyon_shapes::RectangleOrigin::Center,
+            },
+            draw_mode,
+            transform,
+        ),
+        RoomShape::Circle => GeometryBuilder::build_as(
+            &lyon_shapes::Circle {
+                radius: style.size,
+                center: Vec2::ZERO,
+            },
+            draw_mode,
+            transform,
+        ),
+        RoomShape::Hexagon => GeometryBuilder::build_as(
+            &lyon_shapes::RegularPolygon {
+                sides: 6,
+                center: Vec2::ZERO,
+                feature: lyon_shapes::RegularPolygonFeature::Radius(style.size),
+            },
+            draw_mode,
+            transform,
+        ),
+    }
+}
+
+fn room_text(value: &str, text_style: &RoomTextStyle, translation: Vec3) -> Text2dBundle {
+    Text2dBundle {
+        text: Text::with_section(
+            value,
+            TextStyle {
+                font: text_style.font.clone(),
+                font_size: text_style.font_size,
+                color: text_style.color,
+            },
+            TextAlignment {
+                vertical: VerticalAlign::Center,
+                horizontal: HorizontalAlign::Center,
+            },
+        ),
+        transform: Transform::from_translation(translation),
+        ..Default::default()
+    }
+}
+
+/// Draws the shape of rooms whose style changed, and writes their icon and label again, or
+/// every room's when the [`RoomTextStyle`] changed.
+fn draw_rooms(
+    mut commands: Commands,
+    text_style: Res<RoomTextStyle>,
+    rooms: Query<(
+        Entity,
+        &RoomStyle,
+        ChangeTrackers<RoomStyle>,
+        &Transform,
+        Option<&RoomTexts>,
+    )>,
+) {
+    let has_font = text_style.font != Handle::default();
+    for (entity, style, tracker, transform, texts) in rooms.iter() {
+        if !tracker.is_changed() && !text_style.is_changed() {
+            continue;
+        }
+        for text in texts.iter().flat_map(|texts| texts.0.iter()) {
+            commands.entity(*text).despawn_recursive();
+        }
+        let mut new_texts = vec![];
+        let mut room = commands.entity(entity);
+        if tracker.is_changed() {
+            room.insert_bundle(room_shape_bundle(style, *transform));
+        }
+        if has_font {
+            room.with_children(|parent| {
+                if let Some(icon) = &style.icon {
+                    let text = room_text(icon, &text_style, Vec3::Z);
+                    new_texts.push(parent.spawn_bundle(text).id());
+                }
+                if let Some(label) = &style.label {
+                    let below = Vec3::new(0f32, -(style.size + text_style.font_size), 1f32);
+                    let text = room_text(label, &text_style, below);
+                    new_texts.push(parent.spawn_bundle(text).id());
+                }
+            });
+        }
+        room.insert(RoomTexts(new_texts));
+    }
+}
+
+fn draw_connections(
+    mut commands: Commands,
+    style: Res<EdgeStyle>,
+    mut connections: Query<(
+        Entity,
+        ChangeTrackers<ConnectionEntity>,
+        &ConnectionEntity,
+        Option<&EdgeStyleOverride>,
+        Option<ChangeTrackers<EdgeStyleOverride>>,
+        Option<&mut Path>,
+        Option<&mut DrawMode>,
+    )>,
+) {
+    for (entity, tracker, connection, style_override, override_tracker, path, draw_mode) in
+        connections.iter_mut()
+    {
+        let changed = style.is_changed()
+            || tracker.is_changed()
+            || override_tracker.is_some_and(|t| t.is_changed());
+        if !changed {
+            continue;
+        }
+        let new_path = style.path(connection, style_override);
+        let new_draw_mode = style.draw_mode(connection, style_override);
+        match (path, draw_mode) {
+            (Some(mut path), Some(mut draw_mode)) => {
+                *path = new_path;
+                *draw_mode = new_draw_mode;
+            }
+            _ => {
+                commands
+                    .entity(entity)
+                    .insert_bundle(GeometryBuilder::build_as(
+                        &new_path,
+                        new_draw_mode,
+                        Transform::default(),
+                    ));
+            }
+        }
+    }
+}
+
+/// Hides what the viewer never discovered, and dims what it does not see anymore.
+fn apply_fog(
+    viewer: Res<FogViewer>,
+    fogs: Query<&Fog>,
+    mut rooms: Query<(
+        &RoomEntity,
+        &RoomStyle,
+        Option<&RoomTexts>,
+        &mut Visibility,
+        &mut DrawMode,
+    )>,
+    // Visibility is not inherited, so texts are hidden with their room.
+    mut texts: Query<&mut Visibility, (With<Text>, Without<RoomEntity>, Without<ConnectionEntity>)>,
+    edge_style: Res<EdgeStyle>,
+    mut connections: Query<
+        (
+            &ConnectionEntity,
+            Option<&EdgeStyleOverride>,
+            &mut Visibility,
+            &mut DrawMode,
+        ),
+        Without<RoomEntity>,
+    >,
+) {
+    let unknown = Knowledge::default();
+    let knowledge = match (viewer.faction, fogs.get_single()) {
+        // Nothing is known before the first update.
+        (Some(faction), Ok(fog)) => Some(fog.0.knowledge(faction).unwrap_or(&unknown)),
+        _ => None,
+    };
+    for (room, style, room_texts, mut visibility, mut draw_mode) in rooms.iter_mut() {
+        let (discovered, visible) = match knowledge {
+            Some(knowledge) => (
+                knowledge.is_discovered(room.room_id),
+                knowledge.is_visible(room.room_id),
+            ),
+            None => (true, true),
+        };
+        if visibility.is_visible != discovered {
+            visibility.is_visible = discovered;
+        }
+        for text in room_texts.iter().flat_map(|texts| texts.0.iter()) {
+            if let Ok(mut visibility) = texts.get_mut(*text) {
+                if visibility.is_visible != discovered {
+                    visibility.is_visible = discovered;
+                }
+            }
+        }
+        let color = if visible { style.color } else { Color::GRAY };
+        if matches!(*draw_mode, DrawMode::Fill(ref fill) if fill.color != color) {
+            if let DrawMode::Fill(ref mut fill) = *draw_mode {
+                fill.color = color;
+            }
+        }
+    }
+    for (connection, style_override, mut visibility, mut draw_mode) in connections.iter_mut() {
+        let (from, to) = (connection.from, connection.to);
+        let (discovered, visible) = match knowledge {
+            Some(knowledge) => (
+                knowledge.is_edge_discovered(from, to) || knowledge.is_edge_discovered(to, from),
+                knowledge.is_edge_visible(from, to) || knowledge.is_edge_visible(to, from),
+            ),
+            None => (true, true),
+        };
+        if visibility.is_visible != discovered {
+            visibility.is_visible = discovered;
+        }
+        let color = if visible {
+            edge_style.with_override(style_override).color
+        } else {
+            Color::GRAY
+        };
+        if matches!(*draw_mode, DrawMode::Stroke(ref stroke) if stroke.color != color) {
+            if let DrawMode::Stroke(ref mut stroke) = *draw_mode {
+                stroke.color = color;
+            }
+        }
+    }
+}
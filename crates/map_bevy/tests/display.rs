@@ -0,0 +1,183 @@
+use bevy::prelude::*;
+use map::{lock_and_key::LockAndKey, EdgeData, RoomId};
+use map_bevy::{
+    ConnectionEntity, DisplayConnections, DisplayMap, Locks, Map, MapCorePlugin, MapStylePlugin,
+    RoomEntity, RoomStyle,
+};
+
+/// Map entities without any window or renderer.
+fn headless_app() -> App {
+    let mut app = App::new();
+    app.add_plugins(MinimalPlugins);
+    app.add_plugin(MapCorePlugin::<i32>::default());
+    app
+}
+
+fn spawn_map(app: &mut App, map: map::Map<i32>) -> Entity {
+    app.world
+        .spawn()
+        .insert(Map(map))
+        .insert(DisplayMap::default())
+        .insert(DisplayConnections::default())
+        .id()
+}
+
+fn displayed_rooms(app: &mut App) -> Vec<(RoomId, Vec2)> {
+    let mut query = app.world.query::<(&RoomEntity, &Transform)>();
+    let mut rooms: Vec<(RoomId, Vec2)> = query
+        .iter(&app.world)
+        .map(|(room, transform)| (room.room_id, transform.translation.truncate()))
+        .collect();
+    rooms.sort_by_key(|(id, _)| *id);
+    rooms
+}
+
+fn displayed_connections(app: &mut App) -> Vec<(RoomId, RoomId)> {
+    let mut query = app.world.query::<&ConnectionEntity>();
+    let mut connections: Vec<(RoomId, RoomId)> = query
+        .iter(&app.world)
+        .map(|connection| (connection.from, connection.to))
+        .collect();
+    connections.sort();
+    connections
+}
+
+#[test]
+fn room_entities_follow_the_map() {
+    let mut app = headless_app();
+    let mut map = map::Map::default();
+    let a = map.create_raw(0, (0f32, 0f32), vec![]);
+    let b = map.create_raw(0, (50f32, 0f32), vec![]);
+    let c = map.create_raw(0, (0f32, 50f32), vec![]);
+    let entity = spawn_map(&mut app, map);
+    app.update();
+    assert_eq!(
+        displayed_rooms(&mut app),
+        vec![
+            (a, Vec2::new(0f32, 0f32)),
+            (b, Vec2::new(50f32, 0f32)),
+            (c, Vec2::new(0f32, 50f32)),
+        ]
+    );
+
+    let d = {
+        let mut map = app.world.get_mut::<Map<i32>>(entity).unwrap();
+        map.0.remove(b);
+        map.0.set_position(c, (-20f32, 50f32));
+        map.0.create_raw(0, (70f32, 70f32), vec![])
+    };
+    app.update();
+    assert_eq!(
+        displayed_rooms(&mut app),
+        vec![
+            (a, Vec2::new(0f32, 0f32)),
+            (c, Vec2::new(-20f32, 50f32)),
+            (d, Vec2::new(70f32, 70f32)),
+        ]
+    );
+    let display = app.world.get::<DisplayMap>(entity).unwrap();
+    assert_eq!(display.ids.len(), 3);
+    assert_eq!(display.get_entity(b), None);
+}
+
+#[test]
+fn one_connection_entity_per_edge() {
+    let mut app = headless_app();
+    let mut map = map::Map::default();
+    let a = map.create_raw(0, (0f32, 0f32), vec![]);
+    let b = map.create_raw(0, (50f32, 0f32), vec![a]);
+    let c = map.create_raw(0, (100f32, 0f32), vec![b]);
+    map.connect(a, b).unwrap();
+    let entity = spawn_map(&mut app, map);
+    app.update();
+    // The two-way connection between a and b is drawn once.
+    assert_eq!(displayed_connections(&mut app), vec![(a, b), (b, c)]);
+
+    app.world
+        .get_mut::<Map<i32>>(entity)
+        .unwrap()
+        .0
+        .disconnect(c, b);
+    app.update();
+    assert_eq!(displayed_connections(&mut app), vec![(a, b)]);
+
+    app.world.get_mut::<Map<i32>>(entity).unwrap().0.remove(a);
+    app.update();
+    assert_eq!(displayed_connections(&mut app), vec![]);
+    let display = app.world.get::<DisplayConnections>(entity).unwrap();
+    assert!(display.ids.is_empty());
+}
+
+#[test]
+fn connection_entities_carry_edge_data() {
+    let mut app = headless_app();
+    let mut map = map::Map::default();
+    let a = map.create_raw(0, (0f32, 0f32), vec![]);
+    let b = map.create_raw(0, (50f32, 0f32), vec![a]);
+    let c = map.create_raw(0, (100f32, 0f32), vec![]);
+    map.connect(a, b).unwrap();
+    map.connect(b, c).unwrap();
+    let mud = EdgeData {
+        terrain: 2f32,
+        door_time: 0f32,
+    };
+    map.set_edge_data(b, c, mud).unwrap();
+    let entity = spawn_map(&mut app, map);
+    app.update();
+
+    let mut query = app.world.query::<&ConnectionEntity>();
+    let mut connection = |app: &mut App, from: RoomId| {
+        let found = query
+            .iter(&app.world)
+            .find(|c| c.from == from)
+            .map(|c| (c.forward, c.backward, c.locked));
+        found.unwrap()
+    };
+    assert_eq!(
+        connection(&mut app, a),
+        (Some(EdgeData::default()), Some(EdgeData::default()), false)
+    );
+    // Only goes from b to c.
+    assert_eq!(connection(&mut app, b), (Some(mud), None, false));
+
+    let mut locks = LockAndKey::default();
+    locks.locks.insert((a, b), Default::default());
+    app.world.entity_mut(entity).insert(Locks(locks));
+    app.update();
+    assert!(connection(&mut app, a).2);
+    assert!(!connection(&mut app, b).2);
+}
+
+#[test]
+fn room_styles_follow_room_data() {
+    let mut app = headless_app();
+    // Styling needs no renderer, only drawing does.
+    app.add_plugin(MapStylePlugin::<i32>::new(|map, id| RoomStyle {
+        label: Some(map.rooms[&id].data.to_string()),
+        ..Default::default()
+    }));
+    let mut map = map::Map::default();
+    let a = map.create_raw(1, (0f32, 0f32), vec![]);
+    let b = map.create_raw(2, (50f32, 0f32), vec![a]);
+    let entity = spawn_map(&mut app, map);
+    app.update();
+    // Room entities are spawned through commands, styles may only come a frame later.
+    app.update();
+
+    let label = |app: &App, room: RoomId| {
+        let display = app.world.get::<DisplayMap>(entity).unwrap();
+        let room = display.get_entity(room).unwrap();
+        app.world.get::<RoomStyle>(room).unwrap().label.clone()
+    };
+    assert_eq!(label(&app, a).as_deref(), Some("1"));
+    assert_eq!(label(&app, b).as_deref(), Some("2"));
+
+    app.world
+        .get_mut::<Map<i32>>(entity)
+        .unwrap()
+        .0
+        .set_data(b, 7);
+    app.update();
+    assert_eq!(label(&app, a).as_deref(), Some("1"));
+    assert_eq!(label(&app, b).as_deref(), Some("7"));
+}
@@ -0,0 +1,73 @@
+use std::fmt::Write;
+
+use map::Map;
+
+const SVG_MARGIN: f32 = 40f32;
+const SVG_ROOM_SIZE: f32 = 15f32;
+
+/// Graphviz output, positions are pinned so `neato -n` keeps the generated layout.
+pub fn to_dot<T>(map: &Map<T>) -> String {
+    let mut out = String::from("graph map {\n");
+    for id in map.sorted_ids() {
+        let (x, y) = map.rooms[&id].position;
+        let _ = writeln!(out, "    {} [pos=\"{},{}!\"];", id, x, y);
+    }
+    for (from, to) in map.edges() {
+        let forward = map.rooms[&from].connections.contains(&to);
+        let backward = map.rooms[&to].connections.contains(&from);
+        let _ = match (forward, backward) {
+            (true, false) => writeln!(out, "    {} -- {} [dir=forward];", from, to),
+            (false, true) => writeln!(out, "    {} -- {} [dir=forward];", to, from),
+            _ => writeln!(out, "    {} -- {};", from, to),
+        };
+    }
+    out.push_str("}\n");
+    out
+}
+
+/// Standalone picture using the in-game colors.
+pub fn to_svg<T>(map: &Map<T>) -> String {
+    let (mut min, mut max) = ((0f32, 0f32), (0f32, 0f32));
+    for (i, room) in map.rooms.values().enumerate() {
+        let (x, y) = room.position;
+        if i == 0 {
+            min = (x, y);
+            max = (x, y);
+        }
+        min = (min.0.min(x), min.1.min(y));
+        max = (max.0.max(x), max.1.max(y));
+    }
+    let mut out = String::new();
+    let _ = writeln!(
+        out,
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" viewBox=\"{} {} {} {}\">",
+        min.0 - SVG_MARGIN,
+        -max.1 - SVG_MARGIN,
+        max.0 - min.0 + 2f32 * SVG_MARGIN,
+        max.1 - min.1 + 2f32 * SVG_MARGIN
+    );
+    for (from, to) in map.edges() {
+        let (x1, y1) = map.rooms[&from].position;
+        let (x2, y2) = map.rooms[&to].position;
+        // Bevy's y axis points up, svg's points down.
+        let _ = writeln!(
+            out,
+            "  <line x1=\"{}\" y1=\"{}\" x2=\"{}\" y2=\"{}\" stroke=\"orangered\" stroke-width=\"10\"/>",
+            x1, -y1, x2, -y2
+        );
+    }
+    for id in map.sorted_ids() {
+        let (x, y) = map.rooms[&id].position;
+        let _ = writeln!(
+            out,
+            "  <rect id=\"room-{}\" x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" fill=\"lime\"/>",
+            id,
+            x - SVG_ROOM_SIZE,
+            -y - SVG_ROOM_SIZE,
+            2f32 * SVG_ROOM_SIZE,
+            2f32 * SVG_ROOM_SIZE
+        );
+    }
+    out.push_str("</svg>\n");
+    out
+}
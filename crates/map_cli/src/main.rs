@@ -0,0 +1,146 @@
+mod export;
+
+use std::process::exit;
+
+use map::{
+    generator::{Generator, GeneratorConfig},
+    layout::{ForceLayout, LayoutConfig},
+    metrics::MapMetrics,
+    raster::{self, RasterConfig, RoomShape},
+    save::SaveFile,
+    Map,
+};
+use rand::{thread_rng, Rng, SeedableRng};
+use rand_chacha::ChaCha20Rng;
+
+const USAGE: &str = "Generates a map without opening a window.
+
+Usage: map_cli [options]
+
+Options:
+    --seed <u64>              seed of the generation, random if omitted
+    --rooms <n>               growth steps (default 25)
+    --attempts <n>            origin rooms tried per growth step (default 5)
+    --tries <n>               poisson attempts around an origin (default 10)
+    --connect-distance <f32>  connect rooms closer than this (default 50)
+    --spawn-extent <f32>      half size of the first room area (default 30)
+    --relax <n>               force-directed relaxation iterations (default 0)
+    --format <json|dot|svg|ascii>
+                              output format (default json)
+    --help                    print this message
+
+The map is written to stdout, stats are written to stderr.";
+
+enum Format {
+    Json,
+    Dot,
+    Svg,
+    Ascii,
+}
+
+struct Args {
+    seed: u64,
+    config: GeneratorConfig,
+    relax_iterations: usize,
+    format: Format,
+}
+
+fn main() {
+    let args = match parse_args(std::env::args().skip(1)) {
+        Ok(args) => args,
+        Err(message) => {
+            eprintln!("{}\n\n{}", message, USAGE);
+            exit(2);
+        }
+    };
+
+    let mut random = ChaCha20Rng::seed_from_u64(args.seed);
+    let mut map = Map::default();
+    Generator::new(args.config.clone()).generate(&mut map, 0, &mut random);
+    let layout = (args.relax_iterations > 0).then(|| LayoutConfig {
+        iterations: args.relax_iterations,
+        ..Default::default()
+    });
+    if let Some(layout) = &layout {
+        ForceLayout::new(layout.clone()).run(&mut map, &mut random);
+    }
+
+    let output = match args.format {
+        Format::Json => {
+            let save = SaveFile::generated(map.clone(), args.config, args.seed);
+            match layout {
+                Some(layout) => save.with_layout(layout),
+                None => save,
+            }
+            .to_json()
+            .expect("map is always serializable")
+        }
+        Format::Dot => export::to_dot(&map),
+        Format::Svg => export::to_svg(&map),
+        Format::Ascii => {
+            // Same room size as the svg export.
+            let room = |_: &i32| RoomShape::Rectangle {
+                width: 30f32,
+                height: 30f32,
+            };
+            raster::rasterize(&map, &RasterConfig::default(), room).to_ascii()
+        }
+    };
+    println!("{}", output);
+
+    let metrics = MapMetrics::compute(&map);
+    eprintln!("seed: {}", args.seed);
+    eprintln!("rooms: {}", metrics.rooms);
+    eprintln!("edges: {}", metrics.edges);
+    eprintln!("diameter: {}", metrics.diameter);
+    eprintln!("components: {}", metrics.components);
+    eprintln!("average shortest path: {}", metrics.average_shortest_path);
+    eprintln!("max betweenness: {}", metrics.max_betweenness);
+    eprintln!("degree distribution: {:?}", metrics.degree_distribution);
+    eprintln!("clustering coefficient: {}", metrics.clustering_coefficient);
+    eprintln!("dead ends: {}", metrics.dead_ends);
+}
+
+fn parse_args(mut args: impl Iterator<Item = String>) -> Result<Args, String> {
+    let mut parsed = Args {
+        seed: thread_rng().gen::<u64>(),
+        config: GeneratorConfig::default(),
+        relax_iterations: 0,
+        format: Format::Json,
+    };
+    while let Some(arg) = args.next() {
+        if arg == "--help" {
+            println!("{}", USAGE);
+            exit(0);
+        }
+        let value = args
+            .next()
+            .ok_or_else(|| format!("Missing value for `{}`", arg))?;
+        match arg.as_str() {
+            "--seed" => parsed.seed = parse_value(&arg, &value)?,
+            "--rooms" => parsed.config.rooms = parse_value(&arg, &value)?,
+            "--attempts" => parsed.config.attempts_per_room = parse_value(&arg, &value)?,
+            "--tries" => parsed.config.nb_tries = parse_value(&arg, &value)?,
+            "--connect-distance" => parsed.config.connect_distance = parse_value(&arg, &value)?,
+            "--spawn-extent" => parsed.config.spawn_extent = parse_value(&arg, &value)?,
+            "--relax" => parsed.relax_iterations = parse_value(&arg, &value)?,
+            "--format" => {
+                parsed.format = match value.as_str() {
+                    "json" => Format::Json,
+                    "dot" => Format::Dot,
+                    "svg" => Format::Svg,
+                    "ascii" => Format::Ascii,
+                    _ => return Err(format!("Unknown format `{}`", value)),
+                }
+            }
+            _ => return Err(format!("Unknown option `{}`", arg)),
+        }
+    }
+    Ok(parsed)
+}
+
+fn parse_value<V: std::str::FromStr>(arg: &str, value: &str) -> Result<V, String> {
+    value
+        .parse()
+        .map_err(|_| format!("Invalid value `{}` for `{}`", value, arg))
+}
@@ -0,0 +1,22 @@
+use std::process::Command;
+
+fn run(args: &[&str]) -> String {
+    let output = Command::new(env!("CARGO_BIN_EXE_map_cli"))
+        .args(args)
+        .output()
+        .expect("map_cli runs");
+    assert!(output.status.success(), "map_cli {:?} failed", args);
+    String::from_utf8(output.stdout).expect("output is utf-8")
+}
+
+#[test]
+fn same_seed_same_output() {
+    for format in ["json", "dot", "svg", "ascii"] {
+        for relax in ["0", "10"] {
+            let args = ["--seed", "7", "--relax", relax, "--format", format];
+            let first = run(&args);
+            assert!(!first.is_empty());
+            assert_eq!(first, run(&args), "{:?}", args);
+        }
+    }
+}
@@ -1,5 +1,6 @@
 use rand::Rng;
 
+#[derive(Default)]
 pub struct Poisson {}
 
 impl Poisson {
@@ -8,19 +9,19 @@ impl Poisson {
     }
     pub fn compute_new_position(
         &self,
-        existing_points: &Vec<(f32, f32)>,
+        existing_points: &[(f32, f32)],
         near_point: &(f32, f32),
         radius: f32,
         nb_attempts: u32,
         random: &mut impl Rng,
     ) -> Option<(f32, f32)> {
         const EPSILON: f32 = 0.01f32;
-        let seed = random.next_u64() as f32 / std::u64::MAX as f32;
+        let seed = random.next_u64() as f32 / u64::MAX as f32;
         let radius_plus_epsilon = radius + EPSILON;
         let radius_squared = radius * radius;
         for attempt_amount in 0..nb_attempts {
-            let theta = std::f32::consts::TAU
-                * (seed as f32 + attempt_amount as f32 / (nb_attempts as f32));
+            let theta =
+                std::f32::consts::TAU * (seed + attempt_amount as f32 / (nb_attempts as f32));
             let test_point = (
                 near_point.0 + radius_plus_epsilon * theta.cos(),
                 near_point.1 + radius_plus_epsilon * theta.sin(),
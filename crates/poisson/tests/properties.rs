@@ -0,0 +1,42 @@
+use poisson::{distance_squared, Poisson};
+use proptest::prelude::*;
+use rand::{rngs::StdRng, SeedableRng};
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(5000))]
+
+    #[test]
+    fn new_position_respects_radius(
+        seed in any::<u64>(),
+        existing_points in prop::collection::vec((-200f32..200f32, -200f32..200f32), 0..30),
+        near_point in (-200f32..200f32, -200f32..200f32),
+        radius in 1f32..100f32,
+        nb_attempts in 1u32..20,
+    ) {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let position = Poisson::new().compute_new_position(
+            &existing_points,
+            &near_point,
+            radius,
+            nb_attempts,
+            &mut rng,
+        );
+        if let Some(position) = position {
+            for p in existing_points.iter() {
+                prop_assert!(distance_squared(p, &position) > radius * radius);
+            }
+            let distance_to_near = distance_squared(&near_point, &position).sqrt();
+            prop_assert!((distance_to_near - radius).abs() < 0.1f32);
+        }
+
+        let mut rng = StdRng::seed_from_u64(seed);
+        let replayed = Poisson::new().compute_new_position(
+            &existing_points,
+            &near_point,
+            radius,
+            nb_attempts,
+            &mut rng,
+        );
+        prop_assert_eq!(position, replayed);
+    }
+}